@@ -1,6 +1,9 @@
-mod avl;
+use std::io;
 
-fn main() {
-    println!("Hello, world!");
-}
+use trees::repl::{self, Kind};
 
+// Runs the tree REPL on an AVL tree, first replaying any scripts named on
+// the command line
+fn main() -> io::Result<()> {
+    repl::run(Kind::Avl)
+}
@@ -1,16 +1,9 @@
-mod bst;
+use std::io;
 
-struct Point {
-    x: i32,
-    y: i32,
-}
+use trees::repl::{self, Kind};
 
-fn main() {
-    let p = Point{y:-32, x:0};
-    println!("Point is {}, {}", p.x, p.y);
-    let x = -32;
-    let y = 0;
-    let p = Point{y, x};
-    println!("Point is {}, {}", p.x, p.y);
+// Runs the tree REPL on an unbalanced binary search tree, first replaying any
+// scripts named on the command line
+fn main() -> io::Result<()> {
+    repl::run(Kind::Bst)
 }
-
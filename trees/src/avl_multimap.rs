@@ -0,0 +1,206 @@
+//! AVL multimap: an AVL tree that keeps every value inserted under a key,
+//! for keys that repeat, such as the timestamps of an event queue.
+
+use std::borrow::Borrow;
+
+use crate::avl_tree::{self, Tree};
+
+/// AVL tree mapping each key of type K to any number of values of type V.
+/// Values under one key keep the order they were inserted in. Order
+/// statistics count every value, so a key held by n values takes n positions.
+pub struct MultiMap<K, V> {
+    tree: Tree<Slot<K>, V>,
+    // Sequence number of the next insert
+    next: u64,
+}
+
+// A key and the sequence number of the insert that stored it, which orders
+// the values under one key
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Slot<K> {
+    key: K,
+    seq: u64,
+}
+
+impl<K, V> Default for MultiMap<K, V> {
+    fn default() -> MultiMap<K, V> {
+        MultiMap { tree: Tree::default(), next: 0 }
+    }
+}
+
+impl<K: Ord, V> MultiMap<K, V> {
+    /// Returns an empty multimap
+    pub fn new() -> MultiMap<K, V> {
+        MultiMap::default()
+    }
+
+    /// Insert value under key, after any values already stored under it
+    pub fn insert_multi(&mut self, key: K, value: V) {
+        self.tree.insert(Slot { key, seq: self.next }, value);
+        self.next += 1;
+    }
+
+    /// Iterate over the values stored under key in the order they were inserted
+    pub fn get_all<Q>(&self, key: &Q) -> GetAll<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        GetAll(self.tree.span(|slot| slot.key.borrow() < key, |slot| slot.key.borrow() > key))
+    }
+
+    /// Returns the number of values stored under key
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.tree.rank_by(|slot| slot.key.borrow() <= key) - self.rank(key)
+    }
+
+    /// Returns true if any value is stored under key
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_all(key).next().is_some()
+    }
+
+    /// Returns the number of values stored under keys less than key, whether
+    /// or not key is present
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.tree.rank_by(|slot| slot.key.borrow() < key)
+    }
+
+    /// Remove the value stored under key that was inserted first
+    ///
+    /// Returns: the removed value, or None if key was not found
+    pub fn remove_one<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.rank(key);
+        if self.tree.select(index)?.0.key.borrow() != key {
+            return None;
+        }
+        self.tree.delete_at(index).map(|(_, value)| value)
+    }
+
+    /// Remove every value stored under key
+    ///
+    /// Returns: the removed values in the order they were inserted
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (index, count) = (self.rank(key), self.count(key));
+        // Each removal moves the next value under key to the same position
+        (0..count).map(|_| self.tree.delete_at(index).unwrap().1).collect()
+    }
+
+    /// Remove the value under the least key that was inserted first,
+    /// rebalancing as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty multimap
+    pub fn extract_min(&mut self) -> Option<(K, V)> {
+        self.tree.extract_min().map(|(slot, value)| (slot.key, value))
+    }
+
+    /// Remove the value under the greatest key that was inserted last,
+    /// rebalancing as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty multimap
+    pub fn extract_max(&mut self) -> Option<(K, V)> {
+        self.tree.extract_max().map(|(slot, value)| (slot.key, value))
+    }
+}
+
+impl<K, V> MultiMap<K, V> {
+    /// Returns the number of values in the multimap, counting each key once
+    /// for every value stored under it
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the multimap holds no values
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns the number of levels in the underlying tree, 0 when empty
+    pub fn height(&self) -> usize {
+        self.tree.height()
+    }
+
+    /// Returns the (key, value) pair at zero based position i in ascending
+    /// key order, values under one key in the order they were inserted
+    pub fn select(&self, i: usize) -> Option<(&K, &V)> {
+        self.tree.select(i).map(|(slot, value)| (&slot.key, value))
+    }
+
+    /// Iterate over (key, value) pairs in ascending key order, values under
+    /// one key in the order they were inserted
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.tree.iter())
+    }
+}
+
+/// Iterator over the values stored under one key of a MultiMap
+pub struct GetAll<'a, K, V>(avl_tree::Range<'a, Slot<K>, V>);
+
+impl<'a, K: Ord, V> Iterator for GetAll<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for GetAll<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a V> {
+        self.0.next_back().map(|(_, value)| value)
+    }
+}
+
+/// Iterator over borrowed (key, value) pairs of a MultiMap
+pub struct Iter<'a, K, V>(avl_tree::Iter<'a, Slot<K>, V>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(slot, value)| (&slot.key, value))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a MultiMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for MultiMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_multi(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for MultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> MultiMap<K, V> {
+        let mut map = MultiMap::new();
+        map.extend(iter);
+        map
+    }
+}
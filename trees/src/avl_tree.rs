@@ -1,115 +1,150 @@
-use std::{cmp::Ordering, collections::VecDeque};
-pub struct Tree <T>{
-    root: Option<Box<Node<T>>>,
+//! AVL tree: a binary search tree whose subtree heights differ by at most one
+//! at every node, restored by rotations after each insert and delete.
+
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt::{self, Display},
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::bulk;
+use crate::cursor::{self, Branch, Zipper};
+use crate::export::{self, Diagram};
+use crate::pretty::{self, Style};
+use crate::stats;
+pub use crate::traversal::Order;
+use crate::traversal::{self, Shape, Span, Split, Traversal};
+use crate::validate::{self, Violation};
+use crate::OrderedMap;
+
+/// AVL tree mapping keys of type K to values of type V
+pub struct Tree<K, V> {
+    root: Option<Box<Node<K, V>>>,
 }
-struct Node <T>{
-    key: u32,
-    value: T,
+struct Node<K, V> {
+    key: K,
+    value: V,
     balance: i32,
-    left_sub: Tree <T>,
-    right_sub: Tree <T>,
+    // Number of nodes in the subtree rooted here
+    size: usize,
+    left_sub: Tree<K, V>,
+    right_sub: Tree<K, V>,
 }
 
-impl<T> Default for Tree<T> {
-    fn default() -> Tree<T> {
-        Tree {
-            root: None
-        }
+impl<K, V> Default for Tree<K, V> {
+    fn default() -> Tree<K, V> {
+        Tree { root: None }
     }
 }
 
-impl<T> Node <T>{
-    fn new(key: u32, value: T) -> Node<T>{
-        Node {
-            key,
-            value,
-            balance: 0,
-            left_sub: Tree::default(),
-            right_sub: Tree::default(),
+impl<K, V> Node<K, V> {
+    // Recompute size from the sizes of both subtrees
+    fn update_size(&mut self) {
+        self.size = 1 + self.left_sub.len() + self.right_sub.len();
+    }
+
+    fn new(key: K, value: V) -> Node<K, V> {
+        Node { key, value, balance: 0, size: 1, left_sub: Tree::default(), right_sub: Tree::default() }
+    }
+
+    // Returns the subtree on side (Less is left)
+    fn sub_mut(&mut self, side: Ordering) -> &mut Tree<K, V> {
+        match side {
+            Ordering::Less => &mut self.left_sub,
+            _ => &mut self.right_sub,
         }
     }
 }
 
-impl<T> Tree <T>{
-    // Returns a new tree initiated with no root or Node of key
-    pub fn new(root: Option<(u32, T)>) -> Tree<T> {
+// Returns the change in balance factor when a subtree on side grows by one level
+fn tilt(side: Ordering) -> i32 {
+    if side == Ordering::Less {
+        -1
+    } else {
+        1
+    }
+}
+
+impl<K: Ord, V> Tree<K, V> {
+    /// Returns a new tree initiated with no root or Node of key
+    pub fn new(root: Option<(K, V)>) -> Tree<K, V> {
         if let Some((key, value)) = root {
-            Tree {
-                root: Some(Box::new(Node::new(key, value))),
-            }
+            Tree { root: Some(Box::new(Node::new(key, value))) }
         } else {
             Tree::default()
         }
     }
 
-    // TODO: Only need to mess new root and old root balance
+    /// Returns a perfectly balanced tree of the (key, value) pairs of iter,
+    /// built in linear time
+    /// Panics unless keys are strictly ascending
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Tree<K, V> {
+        Tree::from_sorted(bulk::sorted(iter))
+    }
+
+    // Build a perfectly balanced tree of items, whose keys are strictly ascending
+    fn from_sorted(items: Vec<(K, V)>) -> Tree<K, V> {
+        let n = items.len();
+        let root = bulk::build(&mut items.into_iter(), n, &mut |(key, value), left, right| {
+            let (left_sub, right_sub) = (Tree { root: left }, Tree { root: right });
+            Box::new(Node {
+                key,
+                value,
+                balance: bulk::height(right_sub.len()) as i32 - bulk::height(left_sub.len()) as i32,
+                size: 1 + left_sub.len() + right_sub.len(),
+                left_sub,
+                right_sub,
+            })
+        });
+        Tree { root }
+    }
+
+    // Rotate the tree left, making the right child the new root
+    // Balance factors of both nodes are recomputed from their old values
     fn left_rotation(&mut self) {
+        stats::rotation();
         // Take current root node
-        let mut current = self.root.take();
-        // Take and hold right child's left child
-        let hold = current.as_mut().unwrap()
-                    .right_sub.root.as_mut().unwrap()
-                    .left_sub.root.take();
+        let mut current = self.root.take().unwrap();
         // Take right child to be new root
-        let mut new_root = current.as_mut().unwrap().right_sub.root.take();
-        // Set current root's right child to held node
-        current.as_mut().unwrap().right_sub.root = hold;
+        let mut new_root = current.right_sub.root.take().unwrap();
+        // Set current root's right child to new root's left child
+        current.right_sub.root = new_root.left_sub.root.take();
         // Set new balance conditions for old root and new root
-        match new_root.as_ref().unwrap().balance == 0 {
-            // right sub tree was perfectly balanced
-            true => {
-                // new tree will be perfectly balanced
-                current.as_mut().unwrap().balance = 0;
-                new_root.as_mut().unwrap().balance = 0;
-            },
-            // right sub tree was left leaning
-            false => {
-                // Current root will be right leaning, new root will be left leaning
-                current.as_mut().unwrap().balance = 1;
-                new_root.as_mut().unwrap().balance = -1;
-            }
-        }
-        // Set new root's right child to current node
-        new_root.as_mut().unwrap().right_sub.root = current;
+        current.balance -= 1 + new_root.balance.max(0);
+        new_root.balance -= 1 - current.balance.min(0);
+        // Set new root's left child to current node
+        current.update_size();
+        new_root.left_sub.root = Some(current);
+        new_root.update_size();
         // Set self.root to new root
-        self.root = new_root;
+        self.root = Some(new_root);
     }
 
+    // Rotate the tree right, making the left child the new root
+    // Balance factors of both nodes are recomputed from their old values
     fn right_rotation(&mut self) {
+        stats::rotation();
         // Take current root node
-        let mut current = self.root.take();
-        // Take and hold left child's right child
-        let hold = current.as_mut().unwrap()
-                    .left_sub.root.as_mut().unwrap()
-                    .right_sub.root.take();
+        let mut current = self.root.take().unwrap();
         // Take left child to be new root
-        let mut new_root = current.as_mut().unwrap().left_sub.root.take();
-        // Set current root nodes left child to held node
-        current.as_mut().unwrap().left_sub.root = hold;
+        let mut new_root = current.left_sub.root.take().unwrap();
+        // Set current root's left child to new root's right child
+        current.left_sub.root = new_root.right_sub.root.take();
         // Set new balance conditions for old root and new root
-        match new_root.as_ref().unwrap().balance == 0 {
-            // left sub tree was perfectly balanced
-            true => {
-                // new tree will be perfectly balanced
-                current.as_mut().unwrap().balance = 0;
-                new_root.as_mut().unwrap().balance = 0;
-            },
-            // left sub tree was right leaning
-            false => {
-                // Current root will be left leaning, new root will be right leaning
-                current.as_mut().unwrap().balance = -1;
-                current.as_mut().unwrap().balance = 1;
-            }
-        }
-
+        current.balance += 1 - new_root.balance.min(0);
+        new_root.balance += 1 + current.balance.max(0);
         // Set new root's right child to current node
-        new_root.as_mut().unwrap().right_sub.root = current;
+        current.update_size();
+        new_root.right_sub.root = Some(current);
+        new_root.update_size();
         // Set self.root to new root
-        self.root = new_root;
+        self.root = Some(new_root);
     }
 
-    // Balances tree rooted at self
-    // Returns true if tree was rotated else false
+    // Balances tree rooted at self, whose balance is -2 or 2
+    // Returns true if the rotation lowered the height of the tree
     fn balance(&mut self) -> bool {
         let mut bal = self.root.as_ref().unwrap().balance;
         match bal {
@@ -120,150 +155,369 @@ impl<T> Tree <T>{
                 // Tree is left right heavy
                 if bal > 0 {
                     self.root.as_mut().unwrap().left_sub.left_rotation();
-                    self.right_rotation();
-                // Tree is left left heavy (and possibly left right heavy)
-                } else {
-                    self.right_rotation();
                 }
-                true
+                self.right_rotation();
+                // A perfectly balanced left subtree keeps the height (only after delete)
+                bal != 0
             },
+            // Tree is right heavy
             b if b > 1 => {
                 // Since bal > 1, root must have right subtree to unwrap
                 bal = self.root.as_ref().unwrap().right_sub.root.as_ref().unwrap().balance;
                 // Tree is right left heavy
                 if bal < 0 {
                     self.root.as_mut().unwrap().right_sub.right_rotation();
-                    self.left_rotation();
-                // Tree is right right heavy (and possibly right left heavy)
-                } else {
-                    self.left_rotation();
                 }
-                true
+                self.left_rotation();
+                bal != 0
             },
             _ => false,
         }
     }
 
-    // Insert node with key into tree
-    // Returns: true if successfully inserted, false if node with key exists
-    pub fn insert(&mut self, key: u32, value: T) -> bool {
+    // Record that the left subtree grew by one level
+    // Returns true if the height of the tree grew
+    fn left_grew(&mut self) -> bool {
+        let node = self.root.as_mut().unwrap();
+        node.balance -= 1;
+        match node.balance {
+            -1 => true,
+            0 => false,
+            _ => !self.balance(),
+        }
+    }
+
+    // Record that the right subtree grew by one level
+    // Returns true if the height of the tree grew
+    fn right_grew(&mut self) -> bool {
+        let node = self.root.as_mut().unwrap();
+        node.balance += 1;
+        match node.balance {
+            1 => true,
+            0 => false,
+            _ => !self.balance(),
+        }
+    }
+
+    // Record that the left subtree shrank by one level
+    // Returns true if the height of the tree shrank
+    fn left_shrunk(&mut self) -> bool {
+        let node = self.root.as_mut().unwrap();
+        node.balance += 1;
+        match node.balance {
+            1 => false,
+            0 => true,
+            _ => self.balance(),
+        }
+    }
+
+    // Record that the right subtree shrank by one level
+    // Returns true if the height of the tree shrank
+    fn right_shrunk(&mut self) -> bool {
+        let node = self.root.as_mut().unwrap();
+        node.balance -= 1;
+        match node.balance {
+            -1 => false,
+            0 => true,
+            _ => self.balance(),
+        }
+    }
+
+    /// Insert node with key and value into tree
+    ///
+    /// Returns: None if key was not present, else Some with the replaced value
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let replaced = self.insert_node(key, value).err();
+        self.debug_validate();
+        replaced
+    }
+
+    // Insert node with key into tree, rebalancing on the way back up
+    // Returns: Err with the replaced value if node with key exists, else
+    // whether the height of the tree grew
+    fn insert_node(&mut self, key: K, value: V) -> Result<bool, V> {
         match &mut self.root {
             None => {
-                self.root = Some(Box::new(Node {
-                    key,
-                    value,
-                    balance: 0,
-                    left_sub: Tree::default(),
-                    right_sub: Tree::default(),
-                }));
-                true
+                self.root = Some(Box::new(Node::new(key, value)));
+                Ok(true)
             },
             Some(rt) => match key.cmp(&rt.key) {
-                Ordering::Equal => false,
-                // TODO: Check if balance changed and rotate is required
-                Ordering::Less => if rt.left_sub.insert(key, value) {
-                    rt.balance -= 1; 
-                    // Rotate necessary?
-                    true
-                } else {
-                    false
+                Ordering::Equal => Err(mem::replace(&mut rt.value, value)),
+                Ordering::Less => {
+                    let grew = rt.left_sub.insert_node(key, value)?;
+                    rt.size += 1;
+                    Ok(grew && self.left_grew())
                 },
-                Ordering::Greater => if rt.right_sub.insert(key, value) {
-                    rt.balance += 1;
-                    // Rotate?
-                    true
-                } else {
-                    false
+                Ordering::Greater => {
+                    let grew = rt.right_sub.insert_node(key, value)?;
+                    rt.size += 1;
+                    Ok(grew && self.right_grew())
                 },
             },
         }
     }
 
-    // Delete node with key in tree
-    // Returns: true if successfull, else false
-    pub fn delete(&mut self, key: u32) -> bool {
-        match self.root.is_some() {
-            // Empty tree
-            false => false,
-            true => {
-                let current = self;
-                match key.cmp(&current.root.as_ref().unwrap().key) {
-                    // Delete current node
-                    // TODO is it necessary to update balance?
-                    Ordering::Equal => {
-                        match (
-                            current.root.as_ref().unwrap().left_sub.root.is_some(),
-                            current.root.as_ref().unwrap().right_sub.root.is_some(),
-                        ) {
-                            // Current is leaf
-                            (false, false) => current.root = None,
-                            // Current has only left descendents
-                            (true, false) => current.root = current.root.take().unwrap().left_sub.root,
-                            // Current has only right descendents
-                            (false, true) => current.root = current.root.take().unwrap().right_sub.root,
-                            // Current has both descendents
-                            (true, true) => 
-                                current.root.as_mut().unwrap().key = current
-                                    .root
-                                    .as_mut()
-                                    .unwrap()
-                                    .right_sub
-                                    .extract_min()
-                                    .unwrap(),
-                        }
-                        true
-                    },
-                    // Target may be in left subtree
-                    Ordering::Less => if current.root.as_mut().unwrap().left_sub.delete(key) {
-                        current.root.as_mut().unwrap().balance += 1;
-                        // TODO Rotate?
-                        true
-                    } else {
-                        false
-                    },
-                    // Target may be in right subtree
-                    Ordering::Greater => if current.root.as_mut().unwrap().right_sub.delete(key) {
-                        current.root.as_mut().unwrap().balance -= 1;
-                        // TODO Rotate?
-                        true
-                    } else {
-                        false
-                    },
+    // Insert node with key at the empty link path leads to, rebalancing on the
+    // way down. Critical is the index along path of the deepest node whose
+    // balance factor is not 0; every node below it is perfectly balanced and
+    // tips towards the new node, while the critical node either levels out or
+    // tips over and is rotated before the walk goes on. Nodes above it keep
+    // their balance, as the subtree under it keeps its height.
+    // Returns: a reference to the value of the new node
+    fn insert_along(&mut self, path: &[Ordering], critical: Option<usize>, key: K, value: V) -> &mut V {
+        let mut current = self;
+        let mut rest = path;
+        if let Some(critical) = critical {
+            let (above, below) = path.split_at(critical);
+            for &side in above {
+                let node = current.root.as_mut().unwrap();
+                node.size += 1;
+                current = node.sub_mut(side);
+            }
+            let node = current.root.as_mut().unwrap();
+            rest = below;
+            if node.balance == tilt(rest[0]) {
+                // The critical node tips over, so its child on that side was
+                // the root of the taller subtree and is there to rotate
+                let (outer, inner) = (rest[0], rest[1]);
+                node.balance += tilt(outer);
+                let child = node.sub_mut(outer).root.as_mut().unwrap();
+                child.balance = tilt(inner);
+                if outer != inner {
+                    match child.sub_mut(inner).root.as_mut() {
+                        Some(grandchild) => grandchild.balance = tilt(rest[2]),
+                        None => {
+                            // The double rotation lifts the new node itself
+                            // to the top, so it goes in first
+                            child.sub_mut(inner).root = Some(Box::new(Node::new(key, value)));
+                            current.balance();
+                            return &mut current.root.as_mut().unwrap().value;
+                        },
+                    }
                 }
+                // Rotated nodes get their sizes and balance factors from the
+                // rotation, those the new node goes under one more for it
+                current.balance();
+                let steps = if outer == inner {
+                    rest = &rest[2..];
+                    [Some(outer), None]
+                } else {
+                    // The grandchild is on top, the new node under whichever
+                    // of the other two took the subtree it was in
+                    let side = rest[2];
+                    rest = &rest[3..];
+                    [Some(side), Some(side.reverse())]
+                };
+                for side in steps.into_iter().flatten() {
+                    let node = current.root.as_mut().unwrap();
+                    node.size += 1;
+                    current = node.sub_mut(side);
+                }
+            }
+        }
+        for &side in rest {
+            let node = current.root.as_mut().unwrap();
+            node.size += 1;
+            node.balance += tilt(side);
+            current = node.sub_mut(side);
+        }
+        &mut current.root.insert(Box::new(Node::new(key, value))).value
+    }
+
+    /// Returns a reference to the value stored under key, if any
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self;
+        while let Some(node) = &current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => current = &node.right_sub,
+            }
+        }
+        None
+    }
+
+    /// Returns a mutable reference to the value stored under key, if any
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self;
+        while let Some(node) = &mut current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => current = &mut node.left_sub,
+                Ordering::Greater => current = &mut node.right_sub,
             }
         }
+        None
+    }
+
+    /// Returns true if a node with key exists in the tree
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Delete node with key in tree
+    ///
+    /// Returns: Some with the removed key and value, or None if key was not found
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let removed = self.delete_node(key).map(|(entry, _)| entry);
+        self.debug_validate();
+        removed
+    }
+
+    // Delete node with key in tree, rebalancing on the way back up
+    // Returns: None if key was not found, else the removed key and value
+    // and whether the height of the tree shrank
+    fn delete_node<Q>(&mut self, key: &Q) -> Option<((K, V), bool)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.delete_by(&mut |node| key.cmp(node.key.borrow()))
+    }
+
+    // Delete the node at zero based position index in ascending key order,
+    // rebalancing as delete does
+    // Returns: the removed key and value, or None if there is no such node
+    pub(crate) fn delete_at(&mut self, index: usize) -> Option<(K, V)> {
+        let removed = self.delete_by(&mut by_rank(index)).map(|(entry, _)| entry);
+        self.debug_validate();
+        removed
+    }
+
+    // Delete the node direction leads to from the root, which tells on which
+    // side of each node passed the target lies, Equal at the target itself
+    // Returns: as delete_node
+    fn delete_by(&mut self, direction: &mut impl FnMut(&Node<K, V>) -> Ordering) -> Option<((K, V), bool)> {
+        // Empty tree
+        let current = self.root.as_mut()?;
+        match direction(current) {
+            // Delete current node
+            Ordering::Equal => {
+                if current.left_sub.root.is_some() && current.right_sub.root.is_some() {
+                    // Current has both descendents, successor key and value take its place
+                    let (successor, shrunk) = current.right_sub.remove_min();
+                    let removed = (
+                        mem::replace(&mut current.key, successor.key),
+                        mem::replace(&mut current.value, successor.value),
+                    );
+                    current.size -= 1;
+                    return Some((removed, shrunk && self.right_shrunk()));
+                }
+                // Current is a leaf or has a single child which replaces it
+                let mut node = self.root.take().unwrap();
+                self.root = node.left_sub.root.take().or(node.right_sub.root.take());
+                Some(((node.key, node.value), true))
+            },
+            // Target may be in left subtree
+            Ordering::Less => {
+                let (removed, shrunk) = current.left_sub.delete_by(direction)?;
+                current.size -= 1;
+                Some((removed, shrunk && self.left_shrunk()))
+            },
+            // Target may be in right subtree
+            Ordering::Greater => {
+                let (removed, shrunk) = current.right_sub.delete_by(direction)?;
+                current.size -= 1;
+                Some((removed, shrunk && self.right_shrunk()))
+            },
+        }
+    }
+
+    // Unlink the minimum node of a non-empty tree, rebalancing on the way back up
+    // Returns: the node and whether the height of the tree shrank
+    fn remove_min(&mut self) -> (Box<Node<K, V>>, bool) {
+        let current = self.root.as_mut().unwrap();
+        if current.left_sub.root.is_none() {
+            // Replace minimum node with its right child
+            let mut node = self.root.take().unwrap();
+            self.root = node.right_sub.root.take();
+            return (node, true);
+        }
+        let (node, shrunk) = current.left_sub.remove_min();
+        current.size -= 1;
+        (node, shrunk && self.left_shrunk())
+    }
+
+    // Unlink the maximum node of a non-empty tree, rebalancing on the way back up
+    // Returns: the node and whether the height of the tree shrank
+    fn remove_max(&mut self) -> (Box<Node<K, V>>, bool) {
+        let current = self.root.as_mut().unwrap();
+        if current.right_sub.root.is_none() {
+            // Replace maximum node with its left child
+            let mut node = self.root.take().unwrap();
+            self.root = node.left_sub.root.take();
+            return (node, true);
+        }
+        let (node, shrunk) = current.right_sub.remove_max();
+        current.size -= 1;
+        (node, shrunk && self.right_shrunk())
+    }
+
+    /// Check the search tree order of the keys, the subtree sizes nodes store, and that
+    /// stored balance factors match subtree heights that differ by at most one
+    ///
+    /// Returns: Err with every violation found, each with the path to the offending node
+    pub fn validate(&self) -> Result<(), Vec<Violation<'_, K>>> {
+        let violations = validate::check(self.root.as_deref(), None);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
     }
 
-    // TODO: Add rotations
+    // Check every invariant after a change, when built with the validate feature
+    fn debug_validate(&self) {
+        if cfg!(feature = "validate") {
+            assert!(self.validate().is_ok(), "invariants broken: {:?}", validate::problems(self.validate()));
+        }
+    }
 
-    // Find key in self
-    // Returns Vec of nodes visited to find key
-    // Returns empty Vec if key was not found
-    pub fn search(&mut self, key: u32) -> Vec<u32> {
+    /// Find key in self
+    ///
+    /// Returns Vec of nodes visited to find key
+    /// Returns empty Vec if key was not found
+    pub fn search<Q>(&self, key: &Q) -> Vec<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         // Loads visited nodes to vec parameter
         // Returns true if key is found else false
-        fn search_recursive<T>(current: &mut Tree<T>, key: u32, vec: &mut Vec<u32>) -> bool {
-            match current.root.is_some() {
-                false => false,
-                true => {
-                    vec.push(current.root.as_ref().unwrap().key);
-                    match key.cmp(&current.root.as_ref().unwrap().key) {
+        fn search_recursive<'a, K, V, Q>(current: &'a Tree<K, V>, key: &Q, vec: &mut Vec<&'a K>) -> bool
+        where
+            K: Borrow<Q>,
+            Q: Ord + ?Sized,
+        {
+            match &current.root {
+                None => false,
+                Some(node) => {
+                    vec.push(&node.key);
+                    match key.cmp(node.key.borrow()) {
                         // Target is self.root
                         Ordering::Equal => true,
                         // Target may be in right subtree
-                        Ordering::Greater => search_recursive(
-                            &mut current.root.as_mut().unwrap().right_sub, 
-                            key, 
-                            vec
-                        ),
+                        Ordering::Greater => search_recursive(&node.right_sub, key, vec),
                         // Target may be in left subtree
-                        Ordering::Less => search_recursive(
-                            &mut current.root.as_mut().unwrap().left_sub, 
-                            key, 
-                            vec
-                        ),
+                        Ordering::Less => search_recursive(&node.left_sub, key, vec),
                     }
-                }
+                },
             }
         }
         let mut vec = Vec::new();
@@ -273,96 +527,986 @@ impl<T> Tree <T>{
         }
     }
 
-    // Return a vector of keys in pre-order
-    pub fn pre_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.push(self.root.as_ref().unwrap().key);
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.pre_order());
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.pre_order());
-        }
-        vec
+    /// Iterate in ascending key order over the (key, value) pairs whose keys lie in range
+    /// Only subtrees overlapping the range are visited, from either end
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.span(
+            |key| traversal::below(key.borrow(), range.start_bound()),
+            |key| traversal::above(key.borrow(), range.end_bound()),
+        )
     }
 
-    // Return a vector of keys in post-order
-    pub fn post_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.post_order());
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.post_order());
-            vec.push(self.root.as_ref().unwrap().key);
+    // Iterate in ascending key order over the (key, value) pairs whose keys
+    // lie in a range, where below and above report whether a key lies outside it
+    pub(crate) fn span(&self, below: impl Fn(&K) -> bool, above: impl Fn(&K) -> bool) -> Range<'_, K, V> {
+        Range(Span::new(self.root.as_deref(), below, above))
+    }
+
+    /// Returns the (key, value) pair with the greatest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Included(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Included(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the (key, value) pair with the greatest key strictly less than key
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Excluded(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key strictly greater than key
+    pub fn successor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Excluded(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the number of keys in the tree less than key, whether or not key is present
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.locate(key).0
+    }
+
+    // Returns the rank of key and whether key is present
+    fn locate<Q>(&self, key: &Q) -> (usize, bool)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut current = self;
+        while let Some(node) = &current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return (rank + node.left_sub.len(), true),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => {
+                    rank += node.left_sub.len() + 1;
+                    current = &node.right_sub;
+                },
+            }
         }
-        vec
+        (rank, false)
     }
-    
-    // Return a vector of keys in-order
-    pub fn in_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.in_order());
-            vec.push(self.root.as_ref().unwrap().key);
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.in_order());
-        }
-        vec
-    }
-
-    // Return a vector of keys in the ordering a breadth first traversal (left to right)
-    pub fn bft(&mut self) -> Vec<u32> {
-        fn bft_rec<T>(vec: &mut Vec<u32>, que: &mut VecDeque<&Node<T>>) {
-            if !que.is_empty() {
-                let node = que.pop_front().unwrap();
-                vec.push(node.key);
-                if node.left_sub.root.is_some() {
-                    que.push_back(node.left_sub.root.as_ref().unwrap());
-                }
-                if node.right_sub.root.is_some() {
-                    que.push_back(node.right_sub.root.as_ref().unwrap());
+
+    /// Returns the entry for key, for in-place access or insertion
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        // The search only reads the tree: a mutable one could not hand the
+        // whole tree on to a vacant entry. An occupied entry follows the
+        // sides it recorded down to the node without comparing keys again.
+        let mut path = Vec::new();
+        let mut critical = None;
+        let mut current = &*self;
+        while let Some(node) = &current.root {
+            let side = key.cmp(&node.key);
+            if side == Ordering::Equal {
+                let mut node = self.root.as_mut().unwrap();
+                for side in path {
+                    node = node.sub_mut(side).root.as_mut().unwrap();
                 }
-                bft_rec(vec, que)
+                return Entry::Occupied(OccupiedEntry { node });
             }
+            if node.balance != 0 {
+                critical = Some(path.len());
+            }
+            path.push(side);
+            current = if side == Ordering::Less { &node.left_sub } else { &node.right_sub };
         }
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            let mut que: VecDeque<&Node<T>> = VecDeque::new();
-            que.push_back(self.root.as_ref().unwrap());
-            bft_rec(&mut vec, &mut que);
+        Entry::Vacant(VacantEntry { tree: self, key, path, critical })
+    }
+
+    /// Returns a cursor on the least key, or on the ghost position if the tree is empty
+    pub fn cursor_front(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Less);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the greatest key, or on the ghost position if the tree is empty
+    pub fn cursor_back(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Greater);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the least key greater than or equal to key, or on
+    /// the ghost position if there is none
+    pub fn cursor<Q>(&self, key: &Q) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.lower_bound(|node| node.key.borrow() < key);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor that edits the tree, on the least key
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Less);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the greatest key
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Greater);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the least key greater than or
+    /// equal to key, or on the ghost position if there is none
+    pub fn cursor_mut<Q>(&mut self, key: &Q) -> CursorMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.lower_bound(|node| node.key.borrow() < key);
+        cursor
+    }
+
+    /// Return a vector of keys in pre-order
+    pub fn pre_order(&self) -> Vec<&K> {
+        self.keys_in(Order::Pre).collect()
+    }
+
+    /// Return a vector of keys in post-order
+    pub fn post_order(&self) -> Vec<&K> {
+        self.keys_in(Order::Post).collect()
+    }
+
+    /// Return a vector of keys in-order
+    pub fn in_order(&self) -> Vec<&K> {
+        self.keys().collect()
+    }
+
+    /// Return a vector of keys in the ordering a breadth first traversal (left to right)
+    pub fn bft(&self) -> Vec<&K> {
+        self.keys_in(Order::Level).collect()
+    }
+
+    /// Find and extract the minimum key of a tree, rebalancing as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_min(&mut self) -> Option<(K, V)> {
+        // Check if empty tree
+        self.root.as_ref()?;
+        let node = self.remove_min().0;
+        self.debug_validate();
+        Some((node.key, node.value))
+    }
+
+    /// Find and extract the maximum key of a tree, rebalancing as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_max(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        let node = self.remove_max().0;
+        self.debug_validate();
+        Some((node.key, node.value))
+    }
+
+    /// Join left, the (key, value) pair entry and right into one tree, in time
+    /// logarithmic in their sizes
+    /// Panics unless every key in left is less than entry's key and every key
+    /// in right greater
+    pub fn join(left: Tree<K, V>, entry: (K, V), right: Tree<K, V>) -> Tree<K, V> {
+        let (key, value) = entry;
+        assert!(
+            OrderedMap::max(&left).is_none_or(|(max, _)| *max < key)
+                && OrderedMap::min(&right).is_none_or(|(min, _)| *min > key),
+            "keys of left must be less than key and keys of right greater"
+        );
+        let (left_height, right_height) = (left.spine_height(), right.spine_height());
+        let node = Box::new(Node::new(key, value));
+        Tree::join_parts((left, left_height), node, (right, right_height)).0
+    }
+
+    /// Move the keys greater than or equal to key into a new tree, in time
+    /// logarithmic in the size of the tree
+    ///
+    /// Returns: the tree of the moved keys
+    pub fn split_off<Q>(&mut self, key: &Q) -> Tree<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let height = self.spine_height();
+        let (less, found, greater) = mem::take(self).split(height, key);
+        *self = less.0;
+        match found {
+            Some(node) => Tree::join_parts((Tree::default(), 0), node, greater).0,
+            None => greater.0,
         }
-        vec
     }
 
-    // Find and extract the minimum value of a tree
-    // Replaces minimum value node with its right child if necessary
-    // Returns: Some<u32> or None on empty tree
-    pub fn extract_min(&mut self) -> Option<u32> {
+    /// Move every key of other into self, leaving other empty; for keys in
+    /// both, the value from other replaces the one in self
+    /// Takes time logarithmic in the sizes when all keys of one tree are less
+    /// than all keys of the other, and O(m log(n / m + 1)) for m keys in other
+    /// otherwise
+    pub fn append(&mut self, other: &mut Tree<K, V>) {
+        let (mut left, mut right) = (mem::take(self), mem::take(other));
+        let below = |left: &Tree<K, V>, right: &Tree<K, V>| match (OrderedMap::max(left), OrderedMap::min(right)) {
+            (Some((max, _)), Some((min, _))) => max < min,
+            _ => false,
+        };
+        if left.is_empty() || right.is_empty() || below(&left, &right) || below(&right, &left) {
+            if below(&right, &left) {
+                mem::swap(&mut left, &mut right);
+            }
+            if right.is_empty() {
+                *self = left;
+                return;
+            }
+            // The least key of the right tree joins the two
+            let node = right.remove_min().0;
+            let (left_height, right_height) = (left.spine_height(), right.spine_height());
+            *self = Tree::join_parts((left, left_height), node, (right, right_height)).0;
+            return;
+        }
+        let (left_height, right_height) = (left.spine_height(), right.spine_height());
+        *self = Tree::union((left, left_height), (right, right_height)).0;
+    }
+
+    // Split a tree of the given height into the keys less than key, the node
+    // holding key if any, and the keys greater than key, joining the subtrees
+    // cut off on either side of the path to key
+    // Returns: both trees with their heights, and the node holding key
+    fn split<Q>(self, height: usize, key: &Q) -> Parts<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(mut node) = self.root else {
+            return ((Tree::default(), 0), None, (Tree::default(), 0));
+        };
+        let (left_height, right_height) = child_heights(height, node.balance);
+        let left = (mem::take(&mut node.left_sub), left_height);
+        let right = (mem::take(&mut node.right_sub), right_height);
+        match key.cmp(node.key.borrow()) {
+            Ordering::Equal => (left, Some(node), right),
+            Ordering::Less => {
+                let (less, found, greater) = left.0.split(left.1, key);
+                (less, found, Tree::join_parts(greater, node, right))
+            },
+            Ordering::Greater => {
+                let (less, found, greater) = right.0.split(right.1, key);
+                (Tree::join_parts(left, node, less), found, greater)
+            },
+        }
+    }
+
+    // Returns the union of trees a and b with their heights, splitting a at
+    // the key of each node of b; for keys in both, the value from b is kept
+    fn union(a: Measured<K, V>, b: Measured<K, V>) -> Measured<K, V> {
+        if a.0.is_empty() {
+            return b;
+        }
+        let Some(mut node) = b.0.root else {
+            return a;
+        };
+        let (left_height, right_height) = child_heights(b.1, node.balance);
+        let left = (mem::take(&mut node.left_sub), left_height);
+        let right = (mem::take(&mut node.right_sub), right_height);
+        let (less, _, greater) = a.0.split(a.1, &node.key);
+        Tree::join_parts(Tree::union(less, left), node, Tree::union(greater, right))
+    }
+
+    // Join left and right, given with their heights, below node, which holds
+    // a key between theirs. The taller tree is descended along its inner
+    // spine until the heights meet, then rebalanced on the way back up.
+    // Returns: the joined tree and its height
+    fn join_parts(left: Measured<K, V>, node: Box<Node<K, V>>, right: Measured<K, V>) -> Measured<K, V> {
+        let ((mut left, left_height), (mut right, right_height)) = (left, right);
+        if left_height > right_height + 1 {
+            let grew = left.join_right(left_height, node, right, right_height);
+            (left, left_height + grew as usize)
+        } else if right_height > left_height + 1 {
+            let grew = right.join_left(right_height, node, left, left_height);
+            (right, right_height + grew as usize)
+        } else {
+            (Tree::hang(node, (left, left_height), (right, right_height)), left_height.max(right_height) + 1)
+        }
+    }
+
+    // Join self, of the given height, node and right, at least two levels
+    // lower, hanging node where the right spine of self comes down to the
+    // height of right
+    // Returns: true if the height of self grew
+    fn join_right(&mut self, height: usize, node: Box<Node<K, V>>, right: Tree<K, V>, right_height: usize) -> bool {
+        let current = self.root.as_mut().unwrap();
+        let (_, child_height) = child_heights(height, current.balance);
+        current.size += 1 + right.len();
+        if child_height > right_height + 1 {
+            let grew = current.right_sub.join_right(child_height, node, right, right_height);
+            return grew && self.right_grew();
+        }
+        // The new subtree is one level taller than the child it replaces
+        let child = mem::take(&mut current.right_sub);
+        current.right_sub = Tree::hang(node, (child, child_height), (right, right_height));
+        self.right_grew()
+    }
+
+    // Join left, node and self, of the given height and at least two levels
+    // taller than left, hanging node where the left spine of self comes down
+    // to the height of left
+    // Returns: true if the height of self grew
+    fn join_left(&mut self, height: usize, node: Box<Node<K, V>>, left: Tree<K, V>, left_height: usize) -> bool {
+        let current = self.root.as_mut().unwrap();
+        let (child_height, _) = child_heights(height, current.balance);
+        current.size += 1 + left.len();
+        if child_height > left_height + 1 {
+            let grew = current.left_sub.join_left(child_height, node, left, left_height);
+            return grew && self.left_grew();
+        }
+        let child = mem::take(&mut current.left_sub);
+        current.left_sub = Tree::hang(node, (left, left_height), (child, child_height));
+        self.left_grew()
+    }
+
+    // Returns a tree of node above left and right, given with heights that
+    // differ by at most one
+    fn hang(mut node: Box<Node<K, V>>, left: Measured<K, V>, right: Measured<K, V>) -> Tree<K, V> {
+        node.balance = right.1 as i32 - left.1 as i32;
+        node.left_sub = left.0;
+        node.right_sub = right.0;
+        node.update_size();
+        Tree { root: Some(node) }
+    }
+
+    // Returns the height of the tree in time logarithmic in its size,
+    // following the balance factors down its taller side
+    fn spine_height(&self) -> usize {
+        let mut height = 0;
         let mut current = self;
-        let mut ret = None;
-        // Check if empty tree
-        if current.root.is_some() {
-            // While left subtree is not none, move current to left subtree
-            while current.root.as_ref().unwrap().left_sub.root.is_some() {
-                current = &mut current.root.as_mut().unwrap().left_sub;
+        while let Some(node) = &current.root {
+            height += 1;
+            current = if node.balance < 0 { &node.left_sub } else { &node.right_sub };
+        }
+        height
+    }
+}
+
+impl<K, V> Tree<K, V> {
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |node| node.size)
+    }
+
+    /// Returns true if the tree holds no nodes
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the number of levels in the tree, 0 when empty
+    pub fn height(&self) -> usize {
+        traversal::height(self.root.as_deref())
+    }
+
+    /// Returns the bytes of heap memory each entry takes: one boxed node with
+    /// its key, value and links, not counting allocator overhead
+    pub fn node_bytes() -> usize {
+        mem::size_of::<Node<K, V>>()
+    }
+
+    /// Draw the shape of the tree as text in the given style
+    pub fn render(&self, style: &Style) -> String
+    where
+        K: Display,
+    {
+        pretty::render(self.root.as_deref(), style)
+    }
+
+    /// Write the tree as a Graphviz DOT document showing what diagram asks for
+    pub fn to_dot(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::dot(self.root.as_deref(), diagram)
+    }
+
+    /// Write the tree as a standalone SVG image showing what diagram asks for
+    pub fn to_svg(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::svg(self.root.as_deref(), diagram)
+    }
+
+    /// Returns the (key, value) pair at zero based position i in ascending key order
+    pub fn select(&self, mut i: usize) -> Option<(&K, &V)> {
+        let mut current = self;
+        while let Some(node) = &current.root {
+            let left = node.left_sub.len();
+            match i.cmp(&left) {
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => {
+                    i -= left + 1;
+                    current = &node.right_sub;
+                },
             }
-            // Save minimum key
-            ret = Some(current.root.as_ref().unwrap().key);
-            // Remove min node (use take to avoid double borrow)
-            current.root = current.root.take().unwrap().right_sub.root;
         }
-        ret
+        None
     }
 
-    // Find and extract minimum value of a tree
-    // Replaces minimum value node with its left child if necessary
-    // Returns: Some<u32> or None on empty tree
-    pub fn extract_max(&mut self) -> Option<u32> {
+    // Returns the number of keys for which below holds, where below holds for
+    // every key less than one it holds for
+    pub(crate) fn rank_by(&self, below: impl Fn(&K) -> bool) -> usize {
+        let mut rank = 0;
         let mut current = self;
-        let mut ret = None;
-        if current.root.is_some() {
-            while current.root.as_ref().unwrap().right_sub.root.is_some() {
-                current = &mut current.root.as_mut().unwrap().right_sub;
+        while let Some(node) = &current.root {
+            if below(&node.key) {
+                rank += node.left_sub.len() + 1;
+                current = &node.right_sub;
+            } else {
+                current = &node.left_sub;
             }
-            ret = Some(current.root.as_ref().unwrap().key);
-            current.root = current.root.take().unwrap().left_sub.root;
         }
-        ret
+        rank
     }
-}
\ No newline at end of file
+
+    /// Returns the lower median (key, value) pair, or None on empty tree
+    pub fn median(&self) -> Option<(&K, &V)> {
+        self.select(self.len().saturating_sub(1) / 2)
+    }
+
+    /// Iterate over (key, value) pairs in ascending key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.iter_in(Order::In)
+    }
+
+    /// Iterate over (key, value) pairs in the given traversal order
+    pub fn iter_in(&self, order: Order) -> Iter<'_, K, V> {
+        Iter(Traversal::new(self.root.as_deref(), order))
+    }
+
+    /// Iterate over (key, mutable value) pairs in ascending key order
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.iter_mut_in(Order::In)
+    }
+
+    /// Iterate over (key, mutable value) pairs in the given traversal order
+    pub fn iter_mut_in(&mut self, order: Order) -> IterMut<'_, K, V> {
+        IterMut(Traversal::new(self.root.as_deref_mut(), order))
+    }
+
+    /// Consume the tree, yielding owned (key, value) pairs in the given traversal order
+    pub fn into_iter_in(self, order: Order) -> IntoIter<K, V> {
+        IntoIter(Traversal::new(self.root, order))
+    }
+
+    /// Iterate over keys in ascending order
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterate over keys in the given traversal order
+    pub fn keys_in(&self, order: Order) -> Keys<'_, K, V> {
+        Keys(self.iter_in(order))
+    }
+
+    /// Iterate over values in ascending key order
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Iterate over values in the given traversal order
+    pub fn values_in(&self, order: Order) -> Values<'_, K, V> {
+        Values(self.iter_in(order))
+    }
+}
+
+/// A view into a single key of a Tree, which is either present or absent
+pub enum Entry<'a, K, V> {
+    /// The key is present
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// The key is absent
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// An entry whose key is present in the tree
+pub struct OccupiedEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+/// An entry whose key is absent from the tree
+pub struct VacantEntry<'a, K, V> {
+    tree: &'a mut Tree<K, V>,
+    key: K,
+    // Sides taken from the root down to the empty link the key goes in
+    path: Vec<Ordering>,
+    // Index along path of the deepest node whose balance factor is not 0
+    critical: Option<usize>,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    /// Returns the key of the entry
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value for the key, inserting default first if the key is absent
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value for the key, inserting the result of default first if the key is absent
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns the value for the key, inserting V::default() first if the key is absent
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Applies f to the value if the key is present, then returns the entry
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Returns the key of the entry
+    pub fn key(&self) -> &K {
+        &self.node.key
+    }
+
+    /// Returns a reference to the value
+    pub fn get(&self) -> &V {
+        &self.node.value
+    }
+
+    /// Returns a mutable reference to the value
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node.value
+    }
+
+    /// Converts the entry into a mutable reference to the value that lives as long as the tree borrow
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.node.value
+    }
+
+    /// Replaces the value, returning the old one
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(&mut self.node.value, value)
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    /// Returns the key that would be inserted
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes back ownership of the key
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts value under the key, rebalancing as insert does
+    ///
+    /// Returns: a mutable reference to the inserted value
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.tree.insert_along(&self.path, self.critical, self.key, value)
+    }
+}
+
+/// A cursor over the (key, value) pairs of a Tree in ascending key order.
+/// Besides the pairs, it may sit on a ghost position past both ends: moving
+/// past either end reaches the ghost, and moving on from the ghost wraps
+/// around to the other end. Each move takes amortized O(1).
+pub struct Cursor<'a, K, V>(Zipper<&'a Node<K, V>>);
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&'a K> {
+        self.0.handle().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&'a V> {
+        self.0.handle().map(|node| &node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.0.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.0.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+}
+
+/// A cursor that moves as [`Cursor`] does, and also modifies values and
+/// inserts and removes keys next to its position, rebalancing the tree as
+/// insert and delete do. The tree is whole again once the cursor is dropped.
+pub struct CursorMut<'a, K, V> {
+    tree: &'a mut Tree<K, V>,
+    // Holds the nodes of tree, which is left empty while the cursor lives
+    zipper: Zipper<Box<Node<K, V>>>,
+}
+
+impl<'a, K, V> CursorMut<'a, K, V> {
+    // Returns a cursor on the ghost position of tree
+    fn new(tree: &'a mut Tree<K, V>) -> CursorMut<'a, K, V> {
+        CursorMut { zipper: Zipper::new(tree.root.take()), tree }
+    }
+
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&K> {
+        self.zipper.current().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&V> {
+        self.zipper.current().map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value the cursor is on, None on the ghost
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.zipper.current_mut().map(|node| &mut node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.zipper.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.zipper.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+
+    // Returns the position of the cursor in ascending key order, None on the ghost
+    fn index(&self) -> Option<usize> {
+        let node = self.zipper.current()?;
+        let before: usize = self
+            .zipper
+            .path()
+            .filter(|(_, side)| *side == Ordering::Greater)
+            .map(|(ancestor, _)| ancestor.left_sub.len() + 1)
+            .sum();
+        Some(before + node.left_sub.len())
+    }
+
+    // Apply edit to the whole tree, then put the cursor on the node at
+    // position index in ascending key order, or on the ghost if there is none
+    fn edit<T>(&mut self, index: Option<usize>, edit: impl FnOnce(&mut Tree<K, V>) -> T) -> T {
+        self.tree.root = self.zipper.take();
+        let result = edit(self.tree);
+        self.zipper = Zipper::new(self.tree.root.take());
+        if let Some(index) = index {
+            self.zipper.seek(by_rank(index));
+        }
+        result
+    }
+}
+
+impl<K: Ord, V> CursorMut<'_, K, V> {
+    /// Insert key and value just after the cursor, or at the front from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the key the cursor is on and the next
+    pub fn insert_after(&mut self, key: K, value: V) {
+        let next = self.peek_next().map(|(next, _)| next);
+        assert!(cursor::between(self.key(), &key, next), "key must lie strictly between the keys around the cursor");
+        let index = self.index();
+        self.edit(index, |tree| tree.insert(key, value));
+    }
+
+    /// Insert key and value just before the cursor, or at the back from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the previous key and the key the cursor is on
+    pub fn insert_before(&mut self, key: K, value: V) {
+        let prev = self.peek_prev().map(|(prev, _)| prev);
+        assert!(cursor::between(prev, &key, self.key()), "key must lie strictly between the keys around the cursor");
+        let index = self.index().map(|index| index + 1);
+        self.edit(index, |tree| tree.insert(key, value));
+    }
+
+    /// Remove the pair the cursor is on and move to the next key
+    ///
+    /// Returns: the removed key and value, or None on the ghost
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let index = self.index()?;
+        self.edit(Some(index), |tree| tree.delete_at(index))
+    }
+}
+
+impl<K, V> Drop for CursorMut<'_, K, V> {
+    fn drop(&mut self) {
+        self.tree.root = self.zipper.take();
+    }
+}
+
+// Returns a direction leading from the root to the node at zero based
+// position index in ascending key order
+fn by_rank<K, V>(mut index: usize) -> impl FnMut(&Node<K, V>) -> Ordering {
+    move |node| {
+        let left = node.left_sub.len();
+        let side = index.cmp(&left);
+        if side == Ordering::Greater {
+            index -= left + 1;
+        }
+        side
+    }
+}
+
+// A tree and its height, as split and join pass them around
+type Measured<K, V> = (Tree<K, V>, usize);
+
+// Keys less than a split key, the node holding it if any, and keys greater
+type Parts<K, V> = (Measured<K, V>, Option<Box<Node<K, V>>>, Measured<K, V>);
+
+// Returns the heights of the left and right subtrees of a node of the given
+// height and balance factor
+fn child_heights(height: usize, balance: i32) -> (usize, usize) {
+    let left = if balance > 0 { height - 2 } else { height - 1 };
+    let right = if balance < 0 { height - 2 } else { height - 1 };
+    (left, right)
+}
+
+impl<'a, K, V> Split for &'a Node<K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        ((&self.key, &self.value), self.left_sub.root.as_deref(), self.right_sub.root.as_deref())
+    }
+}
+
+impl<K, V> Branch for Node<K, V> {
+    fn subtree(&self, side: Ordering) -> Option<&Self> {
+        match side {
+            Ordering::Less => self.left_sub.root.as_deref(),
+            _ => self.right_sub.root.as_deref(),
+        }
+    }
+
+    fn link(&mut self, side: Ordering) -> &mut Option<Box<Self>> {
+        match side {
+            Ordering::Less => &mut self.left_sub.root,
+            _ => &mut self.right_sub.root,
+        }
+    }
+}
+
+/// Draws the tree in the default [`Style`]. A width, as in `{:40}`, wraps
+/// the drawing to that many columns.
+impl<K: Display, V> fmt::Display for Tree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = Style { width: f.width(), ..Style::default() };
+        f.write_str(&self.render(&style))
+    }
+}
+
+impl<K, V> Shape for &Node<K, V> {
+    fn balance(self) -> Option<i32> {
+        Some(self.balance)
+    }
+
+    fn size(self) -> Option<usize> {
+        Some(self.size)
+    }
+}
+
+impl<'a, K, V> Split for &'a mut Node<K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let Node { key, value, left_sub, right_sub, .. } = self;
+        ((&*key, value), left_sub.root.as_deref_mut(), right_sub.root.as_deref_mut())
+    }
+}
+
+impl<K, V> Split for Box<Node<K, V>> {
+    type Item = (K, V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let node = *self;
+        ((node.key, node.value), node.left_sub.root, node.right_sub.root)
+    }
+}
+
+/// Iterator over borrowed (key, value) pairs of a Tree
+pub struct Iter<'a, K, V>(Traversal<&'a Node<K, V>>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Iterator over (key, mutable value) pairs of a Tree
+pub struct IterMut<'a, K, V>(Traversal<&'a mut Node<K, V>>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Owning iterator over (key, value) pairs of a Tree
+pub struct IntoIter<K, V>(Traversal<Box<Node<K, V>>>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Double ended iterator over the (key, value) pairs of a Tree within a range
+pub struct Range<'a, K, V>(Span<&'a Node<K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+/// Iterator over borrowed keys of a Tree
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+/// Iterator over borrowed values of a Tree
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> IntoIterator for Tree<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        self.into_iter_in(Order::In)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Tree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Tree<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// Extending an empty tree with sorted input builds it in linear time; any
+// other input is inserted pair by pair, replacing the values of keys present
+impl<K: Ord, V> Extend<(K, V)> for Tree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let items: Vec<(K, V)> = iter.into_iter().collect();
+        if self.is_empty() && bulk::ascending(&items) {
+            *self = Tree::from_sorted(items);
+            return;
+        }
+        for (key, value) in items {
+            OrderedMap::insert(self, key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for Tree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Tree<K, V> {
+        let mut tree = Tree::default();
+        tree.extend(iter);
+        tree
+    }
+}
@@ -0,0 +1,370 @@
+use std::{env, hint::black_box, process, time::Instant};
+
+use trees::repl::{self, Kind};
+use trees::{stats, AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+// The generator the integration tests use
+#[path = "../../tests/common/mod.rs"]
+mod common;
+use common::Rng;
+
+const USAGE: &str = "\
+usage: bench [options]
+
+Times inserts, lookups, iteration and deletes on each tree and workload,
+writing one CSV row per phase to stdout.
+
+options:
+  --sizes N,...        numbers of keys inserted (default 1000,10000,100000)
+  --trees NAME,...     bst, avl, sg or sg:a/b for alpha = a/b (default bst,avl,sg)
+  --workloads NAME,... sequential, reverse, random, zipf, window (default all)
+  --runs N             runs per measurement, the fastest is kept (default 3)
+  --seed N             seed for random keys (default 1)
+
+The rotations, rebuilds and rebuilt_nodes columns are left empty unless the
+benchmark is built with --features stats, as counting slows the trees down.";
+
+const HEADER: &str =
+    "tree,workload,size,phase,ops,seconds,ops_per_sec,len,height,rotations,rebuilds,rebuilt_nodes,node_bytes";
+
+// Largest size run on an unbalanced tree for workloads of sorted keys, which
+// grow it into a chain and take quadratic time
+const DEGENERATE_LIMIT: usize = 20_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Workload {
+    // Keys 0, 1, 2, ... inserted, looked up and deleted in ascending order
+    Sequential,
+    // The same keys in descending order
+    Reverse,
+    // Distinct keys in a random order, looked up and deleted in other orders
+    Random,
+    // Keys drawn with Zipf's law, so a few keys make up most inserts and lookups
+    Zipf,
+    // Ascending keys of which only the newest eighth are kept, each insert
+    // past that deleting the oldest key; lookups hit the final window
+    Window,
+}
+
+const WORKLOADS: [(&str, Workload); 5] = [
+    ("sequential", Workload::Sequential),
+    ("reverse", Workload::Reverse),
+    ("random", Workload::Random),
+    ("zipf", Workload::Zipf),
+    ("window", Workload::Window),
+];
+
+struct Options {
+    sizes: Vec<usize>,
+    trees: Vec<Kind>,
+    workloads: Vec<Workload>,
+    runs: usize,
+    seed: u64,
+}
+
+// Keys used by each phase of a workload
+struct Keys {
+    inserts: Vec<u64>,
+    // Number of newest keys kept, if inserts evict old keys
+    window: Option<usize>,
+    lookups: Vec<u64>,
+    deletes: Vec<u64>,
+}
+
+// Measurements of one phase
+struct Phase {
+    name: &'static str,
+    ops: usize,
+    seconds: f64,
+    len: usize,
+    height: usize,
+    counters: stats::Counters,
+}
+
+impl Rng {
+    // Uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+}
+
+// Zipf's law with exponent 1 over ranks 0..n, rank r drawn with weight 1/(r + 1)
+struct Zipf {
+    // Running sums of the weights
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    fn new(n: usize) -> Zipf {
+        let mut total = 0.0;
+        let cumulative = (1..=n)
+            .map(|rank| {
+                total += 1.0 / rank as f64;
+                total
+            })
+            .collect();
+        Zipf { cumulative }
+    }
+
+    // Returns a key for a random rank, scattering popular keys across the key space
+    fn sample(&self, rng: &mut Rng) -> u64 {
+        let target = rng.unit() * self.cumulative.last().unwrap();
+        let rank = self.cumulative.partition_point(|&sum| sum <= target);
+        (rank as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+fn keys(workload: Workload, size: usize, rng: &mut Rng) -> Keys {
+    let ascending: Vec<u64> = (0..size as u64).collect();
+    match workload {
+        Workload::Sequential => {
+            Keys { inserts: ascending.clone(), window: None, lookups: ascending.clone(), deletes: ascending }
+        },
+        Workload::Reverse => {
+            let descending: Vec<u64> = ascending.into_iter().rev().collect();
+            Keys { inserts: descending.clone(), window: None, lookups: descending.clone(), deletes: descending }
+        },
+        Workload::Random => {
+            let mut orders = [ascending.clone(), ascending.clone(), ascending];
+            for order in &mut orders {
+                rng.shuffle(order);
+            }
+            let [inserts, lookups, deletes] = orders;
+            Keys { inserts, window: None, lookups, deletes }
+        },
+        Workload::Zipf => {
+            let zipf = Zipf::new(size);
+            let inserts: Vec<u64> = (0..size).map(|_| zipf.sample(rng)).collect();
+            let lookups = (0..size).map(|_| zipf.sample(rng)).collect();
+            let mut deletes = inserts.clone();
+            deletes.sort_unstable();
+            deletes.dedup();
+            rng.shuffle(&mut deletes);
+            Keys { inserts, window: None, lookups, deletes }
+        },
+        Workload::Window => {
+            let window = (size / 8).max(1);
+            let kept = size.saturating_sub(window) as u64..size as u64;
+            let lookups = (0..size).map(|_| kept.start + u64::from(rng.below(window as u32))).collect();
+            Keys { inserts: ascending, window: Some(window), lookups, deletes: kept.collect() }
+        },
+    }
+}
+
+// What the benchmark needs from a tree beyond the map operations
+trait Subject: OrderedMap<u64, u64> {
+    fn height(&self) -> usize;
+    fn node_bytes(&self) -> usize;
+}
+
+impl Subject for BinarySearchTree<u64, u64> {
+    fn height(&self) -> usize {
+        self.height()
+    }
+
+    fn node_bytes(&self) -> usize {
+        BinarySearchTree::<u64, u64>::node_bytes()
+    }
+}
+
+impl Subject for AvlTree<u64, u64> {
+    fn height(&self) -> usize {
+        self.height()
+    }
+
+    fn node_bytes(&self) -> usize {
+        AvlTree::<u64, u64>::node_bytes()
+    }
+}
+
+impl Subject for SGTree<u64, u64> {
+    fn height(&self) -> usize {
+        self.height()
+    }
+
+    fn node_bytes(&self) -> usize {
+        SGTree::<u64, u64>::node_bytes()
+    }
+}
+
+// Time f on tree, counting the rebalancing it does
+fn phase<T: Subject>(tree: &mut T, name: &'static str, ops: usize, f: impl FnOnce(&mut T)) -> Phase {
+    stats::reset();
+    let start = Instant::now();
+    f(tree);
+    let seconds = start.elapsed().as_secs_f64();
+    Phase { name, ops, seconds, len: tree.len(), height: tree.height(), counters: stats::counters() }
+}
+
+// Run every phase of a workload on fresh trees runs times
+//
+// Returns: the phases of the first run, with the fastest time of any run
+fn measure<T: Subject>(new: impl Fn() -> T, keys: &Keys, runs: usize) -> (Vec<Phase>, usize) {
+    let mut best: Vec<Phase> = Vec::new();
+    let mut node_bytes = 0;
+    for _ in 0..runs {
+        let mut tree = new();
+        node_bytes = tree.node_bytes();
+        let evictions = keys.window.map_or(0, |window| keys.inserts.len().saturating_sub(window));
+        let inserted = phase(&mut tree, "insert", keys.inserts.len() + evictions, |tree| {
+            for (i, key) in keys.inserts.iter().enumerate() {
+                black_box(tree.insert(*key, *key));
+                if let Some(old) = keys.window.and_then(|window| i.checked_sub(window)) {
+                    black_box(tree.remove(&keys.inserts[old]));
+                }
+            }
+        });
+        let len = tree.len();
+        let phases = [
+            inserted,
+            phase(&mut tree, "lookup", keys.lookups.len(), |tree| {
+                for key in &keys.lookups {
+                    black_box(tree.get(key));
+                }
+            }),
+            phase(&mut tree, "iterate", len, |tree| {
+                black_box(tree.iter().fold(0u64, |sum, (_, value)| sum.wrapping_add(*value)));
+            }),
+            phase(&mut tree, "delete", keys.deletes.len(), |tree| {
+                for key in &keys.deletes {
+                    black_box(tree.remove(key));
+                }
+            }),
+        ];
+        if best.is_empty() {
+            best.extend(phases);
+        } else {
+            for (best, phase) in best.iter_mut().zip(phases) {
+                best.seconds = best.seconds.min(phase.seconds);
+            }
+        }
+    }
+    (best, node_bytes)
+}
+
+fn tree_name(tree: Kind) -> String {
+    match tree {
+        Kind::Bst => String::from("bst"),
+        Kind::Avl => String::from("avl"),
+        Kind::Scapegoat(a, b) => format!("sg:{a}/{b}"),
+    }
+}
+
+// Returns the counter columns of a phase, empty unless counting is enabled
+fn counter_fields(counters: stats::Counters) -> String {
+    if stats::ENABLED {
+        format!("{},{},{}", counters.rotations, counters.rebuilds, counters.rebuilt_nodes)
+    } else {
+        String::from(",,")
+    }
+}
+
+// Parses a tree named as the interpreter's backend command does, with a
+// scapegoat tree's alpha written sg:a/b
+fn parse_tree(name: &str) -> Result<Kind, String> {
+    match name.split_once(':') {
+        Some(("sg", alpha)) => match alpha.split_once('/') {
+            Some((a, b)) => repl::parse_kind("sg", &[a, b]),
+            None => Err(format!("invalid alpha {alpha}, expected a/b")),
+        },
+        Some(_) => Err(format!("unknown tree {name}, expected bst, avl, sg or sg:a/b")),
+        None => {
+            repl::parse_kind(name, &[]).map_err(|_| format!("unknown tree {name}, expected bst, avl, sg or sg:a/b"))
+        },
+    }
+}
+
+fn parse_workload(name: &str) -> Result<Workload, String> {
+    WORKLOADS
+        .iter()
+        .find(|(workload, _)| *workload == name)
+        .map(|(_, workload)| *workload)
+        .ok_or_else(|| format!("unknown workload {name}, expected sequential, reverse, random, zipf or window"))
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("invalid number {word}"))
+}
+
+fn parse_list<T>(words: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    words.split(',').map(parse).collect()
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        sizes: vec![1_000, 10_000, 100_000],
+        trees: vec![Kind::Bst, Kind::Avl, Kind::Scapegoat(2, 3)],
+        workloads: WORKLOADS.iter().map(|(_, workload)| *workload).collect(),
+        runs: 3,
+        seed: 1,
+    };
+    let mut args = args;
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+        match flag.as_str() {
+            "--sizes" => options.sizes = parse_list(&value, parse_number)?,
+            "--trees" => options.trees = parse_list(&value, parse_tree)?,
+            "--workloads" => options.workloads = parse_list(&value, parse_workload)?,
+            "--runs" => options.runs = parse_number(&value)?,
+            "--seed" => options.seed = parse_number(&value)?,
+            _ => return Err(format!("unknown option {flag}")),
+        }
+    }
+    if options.runs == 0 || options.sizes.contains(&0) {
+        return Err(String::from("runs and sizes must be positive"));
+    }
+    Ok(options)
+}
+
+// Benchmarks every tree on every workload and size, writing CSV to stdout
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return;
+    }
+    let options = parse_options(args.into_iter()).unwrap_or_else(|message| {
+        eprintln!("error: {message}\n{USAGE}");
+        process::exit(2);
+    });
+
+    println!("{HEADER}");
+    for &workload in &options.workloads {
+        let (workload_name, _) = WORKLOADS.iter().find(|(_, other)| *other == workload).unwrap();
+        for &size in &options.sizes {
+            // Every tree sees the same keys
+            let keys = keys(workload, size, &mut Rng(options.seed ^ size as u64));
+            for &tree in &options.trees {
+                let sorted = matches!(workload, Workload::Sequential | Workload::Reverse | Workload::Window);
+                if tree == Kind::Bst && sorted && size > DEGENERATE_LIMIT {
+                    eprintln!("skipping bst on {workload_name} at size {size}: sorted keys take it quadratic time");
+                    continue;
+                }
+                let (phases, node_bytes) = match tree {
+                    Kind::Bst => measure(BinarySearchTree::default, &keys, options.runs),
+                    Kind::Avl => measure(AvlTree::default, &keys, options.runs),
+                    Kind::Scapegoat(a, b) => measure(|| SGTree::new(a, b, None), &keys, options.runs),
+                };
+                for phase in phases {
+                    println!(
+                        "{},{workload_name},{size},{},{},{:.9},{:.0},{},{},{},{node_bytes}",
+                        tree_name(tree),
+                        phase.name,
+                        phase.ops,
+                        phase.seconds,
+                        phase.ops as f64 / phase.seconds.max(f64::MIN_POSITIVE),
+                        phase.len,
+                        phase.height,
+                        counter_fields(phase.counters),
+                    );
+                }
+            }
+        }
+    }
+}
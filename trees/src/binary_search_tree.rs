@@ -1,226 +1,1064 @@
-use std::{cmp::Ordering, collections::VecDeque};
+//! Unbalanced binary search tree.
 
-#[derive(Default)]
-pub struct Tree {
-    root: Option<Box<Node>>,
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt::{self, Display},
+    iter::Peekable,
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::bulk;
+use crate::cursor::{self, Branch, Zipper};
+use crate::export::{self, Diagram};
+use crate::pretty::{self, Style};
+pub use crate::traversal::Order;
+use crate::traversal::{self, Shape, Span, Split, Traversal};
+use crate::validate::{self, Violation};
+
+/// Unbalanced binary search tree mapping keys of type K to values of type V
+pub struct Tree<K, V> {
+    root: Option<Box<Node<K, V>>>,
 }
-struct Node {
-    key: u32,
-    left_sub: Tree,
-    right_sub: Tree,
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Number of nodes in the subtree rooted here
+    size: usize,
+    left_sub: Tree<K, V>,
+    right_sub: Tree<K, V>,
 }
 
-impl Node {
-    fn new(key: u32) -> Node {
-        Node {
-            key,
-            left_sub: Tree::default(),
-            right_sub: Tree::default(),
-        }
+impl<K, V> Default for Tree<K, V> {
+    fn default() -> Tree<K, V> {
+        Tree { root: None }
     }
 }
 
-impl Tree {
-    // Returns a new tree initiated with no root or Node of key
-    pub fn new(root: Option<u32>) -> Tree {
-        if let Some(key) = root {
-            Tree {
-                root: Some(Box::new(Node::new(key))),
+// Free the nodes one at a time, rotating each left child up until its parent
+// has none, so dropping a deep tree neither recurses once per level nor
+// allocates
+impl<K, V> Drop for Tree<K, V> {
+    fn drop(&mut self) {
+        let mut current = self.root.take();
+        while let Some(mut node) = current {
+            match node.left_sub.root.take() {
+                Some(mut left) => {
+                    node.left_sub.root = left.right_sub.root.take();
+                    left.right_sub.root = Some(node);
+                    current = Some(left);
+                },
+                None => current = node.right_sub.root.take(),
             }
+        }
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Node<K, V> {
+        Node { key, value, size: 1, left_sub: Tree::default(), right_sub: Tree::default() }
+    }
+}
+
+impl<K: Ord, V> Tree<K, V> {
+    /// Returns a new tree initiated with no root or Node of key and value
+    pub fn new(root: Option<(K, V)>) -> Tree<K, V> {
+        if let Some((key, value)) = root {
+            Tree { root: Some(Box::new(Node::new(key, value))) }
         } else {
             Tree::default()
         }
     }
 
-    // Insert node with key into tree
-    // Returns: true if successfully inserted, false if node with key exists
-    pub fn insert(&mut self, key: u32) -> bool {
-        match &mut self.root {
-            None => {
-                self.root = Some(Box::new(Node {
-                    key,
-                    left_sub: Tree::default(),
-                    right_sub: Tree::default(),
-                }));
-                true
-            },
-            Some(rt) => match key.cmp(&rt.key) {
-                Ordering::Equal => false,
-                Ordering::Less => rt.left_sub.insert(key),
-                Ordering::Greater => rt.right_sub.insert(key),
-            },
-        }
-    }
-
-    // Delete node with key in tree
-    // Returns: true if successfull, else false
-    pub fn delete(&mut self, key: u32) -> bool {
-        match self.root.is_some() {
-            // Empty tree
-            false => false,
-            true => {
-                let current = self;
-                match key.cmp(&current.root.as_ref().unwrap().key) {
-                    // Delete current node
-                    Ordering::Equal => {
-                        match (
-                            current.root.as_ref().unwrap().left_sub.root.is_some(),
-                            current.root.as_ref().unwrap().right_sub.root.is_some(),
-                        ) {
-                            // Current is leaf
-                            (false, false) => current.root = None,
-                            // Current has only left descendents
-                            (true, false) => current.root = current.root.take().unwrap().left_sub.root,
-                            // Current has only right descendents
-                            (false, true) => current.root = current.root.take().unwrap().right_sub.root,
-                            // Current has both descendents
-                            (true, true) => 
-                                current.root.as_mut().unwrap().key = current
-                                    .root
-                                    .as_mut()
-                                    .unwrap()
-                                    .right_sub
-                                    .extract_min()
-                                    .unwrap(),
-                        }
-                        true
-                    },
-                    // Target may be in left subtree
-                    Ordering::Less => current.root.as_mut().unwrap().left_sub.delete(key),
-                    // Target may be in right subtree
-                    Ordering::Greater => current.root.as_mut().unwrap().right_sub.delete(key),
-                }
+    /// Returns a perfectly balanced tree of the (key, value) pairs of iter,
+    /// built in linear time
+    /// Panics unless keys are strictly ascending
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Tree<K, V> {
+        Tree::from_sorted(bulk::sorted(iter))
+    }
+
+    // Build a perfectly balanced tree of items, whose keys are strictly ascending
+    fn from_sorted(items: Vec<(K, V)>) -> Tree<K, V> {
+        let n = items.len();
+        let root = bulk::build(&mut items.into_iter(), n, &mut |(key, value), left, right| {
+            let (left_sub, right_sub) = (Tree { root: left }, Tree { root: right });
+            Box::new(Node { key, value, size: 1 + left_sub.len() + right_sub.len(), left_sub, right_sub })
+        });
+        Tree { root }
+    }
+
+    /// Insert node with key and value into tree
+    ///
+    /// Returns: None if key was not present, else Some with the replaced value
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(current) = self.get_mut(&key) {
+            return Some(mem::replace(current, value));
+        }
+        // Key is absent, so every node on the way down gains a descendant
+        let mut current = &mut *self;
+        while current.root.is_some() {
+            let node = current.root.as_mut().unwrap();
+            node.size += 1;
+            current = if key < node.key { &mut node.left_sub } else { &mut node.right_sub };
+        }
+        current.root = Some(Box::new(Node::new(key, value)));
+        self.debug_validate();
+        None
+    }
+
+    /// Returns a reference to the value stored under key, if any
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self;
+        while let Some(node) = &current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => current = &node.right_sub,
             }
         }
+        None
     }
 
-    // Find key in self
-    // Returns Vec of nodes visited to find key
-    // Returns empty Vec if key was not found
-    pub fn search(&mut self, key: u32) -> Vec<u32> {
-        // Loads visited nodes to vec parameter
-        // Returns true if key is found else false
-        fn search_recursive(current: &mut Tree, key: u32, vec: &mut Vec<u32>) -> bool {
-            match current.root.is_some() {
-                false => false,
-                true => {
-                    vec.push(current.root.as_ref().unwrap().key);
-                    match key.cmp(&current.root.as_ref().unwrap().key) {
-                        // Target is self.root
-                        Ordering::Equal => true,
-                        // Target may be in right subtree
-                        Ordering::Greater => search_recursive(
-                            &mut current.root.as_mut().unwrap().right_sub, 
-                            key, 
-                            vec
-                        ),
-                        // Target may be in left subtree
-                        Ordering::Less => search_recursive(
-                            &mut current.root.as_mut().unwrap().left_sub, 
-                            key, 
-                            vec
-                        ),
-                    }
-                }
+    /// Returns a mutable reference to the value stored under key, if any
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self;
+        while let Some(node) = &mut current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => current = &mut node.left_sub,
+                Ordering::Greater => current = &mut node.right_sub,
             }
         }
-        let mut vec = Vec::new();
-        match search_recursive(self, key, &mut vec) {
-            false => Vec::new(),
-            true => vec,
+        None
+    }
+
+    /// Returns true if a node with key exists in the tree
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Remove node with key from tree
+    ///
+    /// Returns: Some with the removed key and value, or None if key was not found
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let removed = self.remove_by(|node| key.cmp(node.key.borrow()));
+        self.debug_validate();
+        Some(removed)
+    }
+
+    // Remove the node direction leads to from the root, which tells on which
+    // side of each node passed the target lies, Equal at the target itself
+    // Returns: the removed key and value
+    fn remove_by(&mut self, mut direction: impl FnMut(&Node<K, V>) -> Ordering) -> (K, V) {
+        // The target is present, so every node on the way down loses a descendant
+        let mut current = self;
+        loop {
+            let side = direction(current.root.as_ref().unwrap());
+            if side == Ordering::Equal {
+                return current.unlink();
+            }
+            let node = current.root.as_mut().unwrap();
+            node.size -= 1;
+            current = if side == Ordering::Less { &mut node.left_sub } else { &mut node.right_sub };
         }
     }
 
-    // Return a vector of keys in pre-order
-    pub fn pre_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.push(self.root.as_ref().unwrap().key);
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.pre_order());
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.pre_order());
+    // Remove the root node, replacing it with a child or its successor
+    // Returns: the removed key and value
+    fn unlink(&mut self) -> (K, V) {
+        let current = self.root.as_mut().unwrap();
+        if current.left_sub.root.is_some() && current.right_sub.root.is_some() {
+            // Current has both descendents, successor key and value take its place
+            let (key, value) = current.right_sub.extract_min().unwrap();
+            current.size -= 1;
+            return (mem::replace(&mut current.key, key), mem::replace(&mut current.value, value));
         }
-        vec
+        // Current is a leaf or has a single child which replaces it
+        let mut node = self.root.take().unwrap();
+        self.root = node.left_sub.root.take().or(node.right_sub.root.take());
+        (node.key, node.value)
     }
 
-    // Return a vector of keys in post-order
-    pub fn post_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.post_order());
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.post_order());
-            vec.push(self.root.as_ref().unwrap().key);
+    /// Delete node with key in tree
+    ///
+    /// Returns: true if successfull, else false
+    pub fn delete<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove(key).is_some()
+    }
+
+    /// Check the search tree order of the keys and the subtree sizes nodes store
+    ///
+    /// Returns: Err with every violation found, each with the path to the offending node
+    pub fn validate(&self) -> Result<(), Vec<Violation<'_, K>>> {
+        let violations = validate::check(self.root.as_deref(), None);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
         }
-        vec
     }
 
-    // Return a vector of keys in-order
-    pub fn in_order(&mut self) -> Vec<u32> {
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            vec.append(&mut self.root.as_mut().unwrap().left_sub.in_order());
-            vec.push(self.root.as_ref().unwrap().key);
-            vec.append(&mut self.root.as_mut().unwrap().right_sub.in_order());
+    // Check every invariant after a change, when built with the validate feature
+    fn debug_validate(&self) {
+        if cfg!(feature = "validate") {
+            assert!(self.validate().is_ok(), "invariants broken: {:?}", validate::problems(self.validate()));
         }
-        vec
     }
 
-    // Return a vector of keys in the ordering a breadth first traversal (left to right)
-    pub fn bft(&mut self) -> Vec<u32> {
-        fn bft_rec(vec: &mut Vec<u32>, que: &mut VecDeque<&Node>) {
-            if !que.is_empty() {
-                let node = que.pop_front().unwrap();
-                vec.push(node.key);
-                if node.left_sub.root.is_some() {
-                    que.push_back(node.left_sub.root.as_ref().unwrap());
-                }
-                if node.right_sub.root.is_some() {
-                    que.push_back(node.right_sub.root.as_ref().unwrap());
-                }
-                bft_rec(vec, que)
+    /// Find key in self
+    ///
+    /// Returns Vec of nodes visited to find key
+    /// Returns empty Vec if key was not found
+    pub fn search<Q>(&self, key: &Q) -> Vec<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut visited = Vec::new();
+        let mut current = self;
+        while let Some(node) = &current.root {
+            visited.push(&node.key);
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return visited,
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => current = &node.right_sub,
             }
         }
-        let mut vec = Vec::new();
-        if self.root.is_some() {
-            let mut que: VecDeque<&Node> = VecDeque::new();
-            que.push_back(self.root.as_ref().unwrap());
-            bft_rec(&mut vec, &mut que);
-        }
-        vec
+        Vec::new()
+    }
+
+    /// Iterate in ascending key order over the (key, value) pairs whose keys lie in range
+    /// Only subtrees overlapping the range are visited, from either end
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range(Span::new(
+            self.root.as_deref(),
+            |key| traversal::below(key.borrow(), range.start_bound()),
+            |key| traversal::above(key.borrow(), range.end_bound()),
+        ))
     }
 
-    // Find and extract the minimum value of a tree
-    // Replaces minimum value node with its right child if necessary
-    // Returns: Some<u32> or None on empty tree
-    pub fn extract_min(&mut self) -> Option<u32> {
+    /// Returns the (key, value) pair with the greatest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Included(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Included(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the (key, value) pair with the greatest key strictly less than key
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Excluded(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key strictly greater than key
+    pub fn successor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Excluded(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the number of keys in the tree less than key, whether or not key is present
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
         let mut current = self;
-        let mut ret = None;
-        // Check if empty tree
-        if current.root.is_some() {
-            // While left subtree is not none, move current to left subtree
-            while current.root.as_ref().unwrap().left_sub.root.is_some() {
-                current = &mut current.root.as_mut().unwrap().left_sub;
+        while let Some(node) = &current.root {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return rank + node.left_sub.len(),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => {
+                    rank += node.left_sub.len() + 1;
+                    current = &node.right_sub;
+                },
             }
-            // Save minimum key
-            ret = Some(current.root.as_ref().unwrap().key);
-            // Remove min node (use take to avoid double borrow)
-            current.root = current.root.take().unwrap().right_sub.root;
         }
-        ret
+        rank
+    }
+
+    /// Returns a cursor on the least key, or on the ghost position if the tree is empty
+    pub fn cursor_front(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Less);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the greatest key, or on the ghost position if the tree is empty
+    pub fn cursor_back(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Greater);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the least key greater than or equal to key, or on
+    /// the ghost position if there is none
+    pub fn cursor<Q>(&self, key: &Q) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.lower_bound(|node| node.key.borrow() < key);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor that edits the tree, on the least key
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Less);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the greatest key
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Greater);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the least key greater than or
+    /// equal to key, or on the ghost position if there is none
+    pub fn cursor_mut<Q>(&mut self, key: &Q) -> CursorMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.lower_bound(|node| node.key.borrow() < key);
+        cursor
+    }
+
+    /// Return a vector of keys in pre-order
+    pub fn pre_order(&self) -> Vec<&K> {
+        self.keys_in(Order::Pre).collect()
+    }
+
+    /// Return a vector of keys in post-order
+    pub fn post_order(&self) -> Vec<&K> {
+        self.keys_in(Order::Post).collect()
+    }
+
+    /// Return a vector of keys in-order
+    pub fn in_order(&self) -> Vec<&K> {
+        self.keys().collect()
+    }
+
+    /// Return a vector of keys in the ordering a breadth first traversal (left to right)
+    pub fn bft(&self) -> Vec<&K> {
+        self.keys_in(Order::Level).collect()
+    }
+
+    /// Find and extract the minimum key of a tree
+    /// Replaces minimum key node with its right child if necessary
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_min(&mut self) -> Option<(K, V)> {
+        let mut current = &mut *self;
+        // Check if empty tree
+        current.root.as_ref()?;
+        // While left subtree is not none, move current to left subtree
+        while current.root.as_ref().unwrap().left_sub.root.is_some() {
+            let node = current.root.as_mut().unwrap();
+            node.size -= 1;
+            current = &mut node.left_sub;
+        }
+        // Remove min node (use take to avoid double borrow)
+        let mut node = current.root.take().unwrap();
+        current.root = node.right_sub.root.take();
+        self.debug_validate();
+        Some((node.key, node.value))
+    }
+
+    /// Find and extract maximum key of a tree
+    /// Replaces maximum key node with its left child if necessary
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_max(&mut self) -> Option<(K, V)> {
+        let mut current = &mut *self;
+        current.root.as_ref()?;
+        while current.root.as_ref().unwrap().right_sub.root.is_some() {
+            let node = current.root.as_mut().unwrap();
+            node.size -= 1;
+            current = &mut node.right_sub;
+        }
+        let mut node = current.root.take().unwrap();
+        current.root = node.left_sub.root.take();
+        self.debug_validate();
+        Some((node.key, node.value))
     }
 
-    // Find and extract minimum value of a tree
-    // Replaces minimum value node with its left child if necessary
-    // Returns: Some<u32> or None on empty tree
-    pub fn extract_max(&mut self) -> Option<u32> {
+    /// Iterate in ascending key order over the (key, value) pairs whose keys
+    /// are in self or other, taking the pair from self for keys in both
+    /// Takes time linear in the sizes of both trees
+    pub fn union<'a>(&'a self, other: &'a Tree<K, V>) -> Union<'a, K, V> {
+        Union(Merge::new(self.iter(), other.iter(), true, true, true))
+    }
+
+    /// Iterate in ascending key order over the (key, value) pairs of self
+    /// whose keys are also in other
+    /// Takes time linear in the sizes of both trees. Searching the larger tree
+    /// for each key of the smaller would not pay, as an unbalanced tree may
+    /// be as deep as it is large.
+    pub fn intersection<'a>(&'a self, other: &'a Tree<K, V>) -> Intersection<'a, K, V> {
+        Intersection(Merge::new(self.iter(), other.iter(), false, true, false))
+    }
+
+    /// Iterate in ascending key order over the (key, value) pairs of self
+    /// whose keys are not in other
+    /// Takes time linear in the sizes of both trees
+    pub fn difference<'a>(&'a self, other: &'a Tree<K, V>) -> Difference<'a, K, V> {
+        Difference(Merge::new(self.iter(), other.iter(), true, false, false))
+    }
+
+    /// Iterate in ascending key order over the (key, value) pairs whose keys
+    /// are in exactly one of self and other
+    /// Takes time linear in the sizes of both trees
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Tree<K, V>) -> SymmetricDifference<'a, K, V> {
+        SymmetricDifference(Merge::new(self.iter(), other.iter(), true, false, true))
+    }
+
+    /// Returns true if every key of self is also in other
+    pub fn is_subset(&self, other: &Tree<K, V>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    /// Returns true if self and other have no key in common
+    pub fn is_disjoint(&self, other: &Tree<K, V>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Consume both trees into a perfectly balanced one of the pairs whose
+    /// keys are in either, keeping the value from self for keys in both
+    /// Takes time linear in the sizes of both trees
+    pub fn into_union(self, other: Tree<K, V>) -> Tree<K, V> {
+        Tree::from_sorted(Merge::new(self.into_iter(), other.into_iter(), true, true, true).collect())
+    }
+
+    /// Consume both trees into a perfectly balanced one of the pairs of self
+    /// whose keys are also in other
+    /// Takes time linear in the sizes of both trees
+    pub fn into_intersection(self, other: Tree<K, V>) -> Tree<K, V> {
+        Tree::from_sorted(Merge::new(self.into_iter(), other.into_iter(), false, true, false).collect())
+    }
+
+    /// Consume both trees into a perfectly balanced one of the pairs of self
+    /// whose keys are not in other
+    /// Takes time linear in the sizes of both trees
+    pub fn into_difference(self, other: Tree<K, V>) -> Tree<K, V> {
+        Tree::from_sorted(Merge::new(self.into_iter(), other.into_iter(), true, false, false).collect())
+    }
+
+    /// Consume both trees into a perfectly balanced one of the pairs whose
+    /// keys are in exactly one of them
+    /// Takes time linear in the sizes of both trees
+    pub fn into_symmetric_difference(self, other: Tree<K, V>) -> Tree<K, V> {
+        Tree::from_sorted(Merge::new(self.into_iter(), other.into_iter(), true, false, true).collect())
+    }
+}
+
+impl<K, V> Tree<K, V> {
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |node| node.size)
+    }
+
+    /// Returns true if the tree holds no nodes
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the number of levels in the tree, 0 when empty
+    pub fn height(&self) -> usize {
+        traversal::height(self.root.as_deref())
+    }
+
+    /// Returns the bytes of heap memory each entry takes: one boxed node with
+    /// its key, value and links, not counting allocator overhead
+    pub fn node_bytes() -> usize {
+        mem::size_of::<Node<K, V>>()
+    }
+
+    /// Draw the shape of the tree as text in the given style
+    pub fn render(&self, style: &Style) -> String
+    where
+        K: Display,
+    {
+        pretty::render(self.root.as_deref(), style)
+    }
+
+    /// Write the tree as a Graphviz DOT document showing what diagram asks for
+    pub fn to_dot(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::dot(self.root.as_deref(), diagram)
+    }
+
+    /// Write the tree as a standalone SVG image showing what diagram asks for
+    pub fn to_svg(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::svg(self.root.as_deref(), diagram)
+    }
+
+    /// Returns the (key, value) pair at zero based position i in ascending key order
+    pub fn select(&self, mut i: usize) -> Option<(&K, &V)> {
         let mut current = self;
-        let mut ret = None;
-        if current.root.is_some() {
-            while current.root.as_ref().unwrap().right_sub.root.is_some() {
-                current = &mut current.root.as_mut().unwrap().right_sub;
+        while let Some(node) = &current.root {
+            let left = node.left_sub.len();
+            match i.cmp(&left) {
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Less => current = &node.left_sub,
+                Ordering::Greater => {
+                    i -= left + 1;
+                    current = &node.right_sub;
+                },
+            }
+        }
+        None
+    }
+
+    /// Returns the lower median (key, value) pair, or None on empty tree
+    pub fn median(&self) -> Option<(&K, &V)> {
+        self.select(self.len().saturating_sub(1) / 2)
+    }
+
+    /// Iterate over (key, value) pairs in ascending key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.iter_in(Order::In)
+    }
+
+    /// Iterate over (key, value) pairs in the given traversal order
+    pub fn iter_in(&self, order: Order) -> Iter<'_, K, V> {
+        Iter(Traversal::new(self.root.as_deref(), order))
+    }
+
+    /// Iterate over (key, mutable value) pairs in ascending key order
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.iter_mut_in(Order::In)
+    }
+
+    /// Iterate over (key, mutable value) pairs in the given traversal order
+    pub fn iter_mut_in(&mut self, order: Order) -> IterMut<'_, K, V> {
+        IterMut(Traversal::new(self.root.as_deref_mut(), order))
+    }
+
+    /// Consume the tree, yielding owned (key, value) pairs in the given traversal order
+    pub fn into_iter_in(mut self, order: Order) -> IntoIter<K, V> {
+        IntoIter(Traversal::new(self.root.take(), order))
+    }
+
+    /// Iterate over keys in ascending order
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterate over keys in the given traversal order
+    pub fn keys_in(&self, order: Order) -> Keys<'_, K, V> {
+        Keys(self.iter_in(order))
+    }
+
+    /// Iterate over values in ascending key order
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Iterate over values in the given traversal order
+    pub fn values_in(&self, order: Order) -> Values<'_, K, V> {
+        Values(self.iter_in(order))
+    }
+}
+
+impl<'a, K, V> Split for &'a Node<K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        ((&self.key, &self.value), self.left_sub.root.as_deref(), self.right_sub.root.as_deref())
+    }
+}
+
+impl<K, V> Branch for Node<K, V> {
+    fn subtree(&self, side: Ordering) -> Option<&Self> {
+        match side {
+            Ordering::Less => self.left_sub.root.as_deref(),
+            _ => self.right_sub.root.as_deref(),
+        }
+    }
+
+    fn link(&mut self, side: Ordering) -> &mut Option<Box<Self>> {
+        match side {
+            Ordering::Less => &mut self.left_sub.root,
+            _ => &mut self.right_sub.root,
+        }
+    }
+}
+
+/// Draws the tree in the default [`Style`]. A width, as in `{:40}`, wraps
+/// the drawing to that many columns.
+impl<K: Display, V> fmt::Display for Tree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = Style { width: f.width(), ..Style::default() };
+        f.write_str(&self.render(&style))
+    }
+}
+
+impl<K, V> Shape for &Node<K, V> {
+    fn size(self) -> Option<usize> {
+        Some(self.size)
+    }
+}
+
+impl<'a, K, V> Split for &'a mut Node<K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let Node { key, value, left_sub, right_sub, .. } = self;
+        ((&*key, value), left_sub.root.as_deref_mut(), right_sub.root.as_deref_mut())
+    }
+}
+
+impl<K, V> Split for Box<Node<K, V>> {
+    type Item = (K, V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let mut node = *self;
+        let (left, right) = (node.left_sub.root.take(), node.right_sub.root.take());
+        ((node.key, node.value), left, right)
+    }
+}
+
+/// Iterator over borrowed (key, value) pairs of a Tree
+pub struct Iter<'a, K, V>(Traversal<&'a Node<K, V>>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Iterator over (key, mutable value) pairs of a Tree
+pub struct IterMut<'a, K, V>(Traversal<&'a mut Node<K, V>>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Owning iterator over (key, value) pairs of a Tree
+pub struct IntoIter<K, V>(Traversal<Box<Node<K, V>>>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Double ended iterator over the (key, value) pairs of a Tree within a range
+pub struct Range<'a, K, V>(Span<&'a Node<K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+// Ascending walk over two ascending sequences of (key, value) pairs, yielding
+// the pairs whose keys are only in the first, in both, or only in the second,
+// as chosen; of a key in both, the pair from the first is yielded
+struct Merge<I: Iterator> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+    only_a: bool,
+    both: bool,
+    only_b: bool,
+}
+
+impl<I: Iterator> Merge<I> {
+    fn new(a: I, b: I, only_a: bool, both: bool, only_b: bool) -> Merge<I> {
+        Merge { a: a.peekable(), b: b.peekable(), only_a, both, only_b }
+    }
+}
+
+impl<I, K: Ord, V> Iterator for Merge<I>
+where
+    I: Iterator<Item = (K, V)>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            let side = match (self.a.peek(), self.b.peek()) {
+                (None, None) => return None,
+                // Nothing left to yield once the sequence whose keys are kept runs out
+                (Some(_), None) if !self.only_a => return None,
+                (None, Some(_)) if !self.only_b => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((a, _)), Some((b, _))) => a.cmp(b),
+            };
+            match side {
+                Ordering::Less => {
+                    let item = self.a.next();
+                    if self.only_a {
+                        return item;
+                    }
+                },
+                Ordering::Greater => {
+                    let item = self.b.next();
+                    if self.only_b {
+                        return item;
+                    }
+                },
+                Ordering::Equal => {
+                    self.b.next();
+                    let item = self.a.next();
+                    if self.both {
+                        return item;
+                    }
+                },
             }
-            ret = Some(current.root.as_ref().unwrap().key);
-            current.root = current.root.take().unwrap().left_sub.root;
         }
-        ret
+    }
+}
+
+/// Lazy iterator over the union of two Trees, see [`Tree::union`]
+pub struct Union<'a, K, V>(Merge<Iter<'a, K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Union<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Lazy iterator over the intersection of two Trees, see [`Tree::intersection`]
+pub struct Intersection<'a, K, V>(Merge<Iter<'a, K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Intersection<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Lazy iterator over the difference of two Trees, see [`Tree::difference`]
+pub struct Difference<'a, K, V>(Merge<Iter<'a, K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Difference<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Lazy iterator over the symmetric difference of two Trees, see
+/// [`Tree::symmetric_difference`]
+pub struct SymmetricDifference<'a, K, V>(Merge<Iter<'a, K, V>>);
+
+impl<'a, K: Ord, V> Iterator for SymmetricDifference<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// A cursor over the (key, value) pairs of a Tree in ascending key order.
+/// Besides the pairs, it may sit on a ghost position past both ends: moving
+/// past either end reaches the ghost, and moving on from the ghost wraps
+/// around to the other end. Each move takes amortized O(1).
+pub struct Cursor<'a, K, V>(Zipper<&'a Node<K, V>>);
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&'a K> {
+        self.0.handle().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&'a V> {
+        self.0.handle().map(|node| &node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.0.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.0.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+}
+
+/// A cursor that moves as [`Cursor`] does, and also modifies values and
+/// inserts and removes keys next to its position. The tree is whole again
+/// once the cursor is dropped.
+pub struct CursorMut<'a, K, V> {
+    tree: &'a mut Tree<K, V>,
+    // Holds the nodes of tree, which is left empty while the cursor lives
+    zipper: Zipper<Box<Node<K, V>>>,
+}
+
+impl<'a, K, V> CursorMut<'a, K, V> {
+    // Returns a cursor on the ghost position of tree
+    fn new(tree: &'a mut Tree<K, V>) -> CursorMut<'a, K, V> {
+        CursorMut { zipper: Zipper::new(tree.root.take()), tree }
+    }
+
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&K> {
+        self.zipper.current().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&V> {
+        self.zipper.current().map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value the cursor is on, None on the ghost
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.zipper.current_mut().map(|node| &mut node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.zipper.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.zipper.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+
+    // Returns the position of the cursor in ascending key order, None on the ghost
+    fn index(&self) -> Option<usize> {
+        let node = self.zipper.current()?;
+        let before: usize = self
+            .zipper
+            .path()
+            .filter(|(_, side)| *side == Ordering::Greater)
+            .map(|(ancestor, _)| ancestor.left_sub.len() + 1)
+            .sum();
+        Some(before + node.left_sub.len())
+    }
+
+    // Apply edit to the whole tree, then put the cursor on the node at
+    // position index in ascending key order, or on the ghost if there is none
+    fn edit<T>(&mut self, index: Option<usize>, edit: impl FnOnce(&mut Tree<K, V>) -> T) -> T {
+        self.tree.root = self.zipper.take();
+        let result = edit(self.tree);
+        self.zipper = Zipper::new(self.tree.root.take());
+        if let Some(index) = index {
+            self.zipper.seek(by_rank(index));
+        }
+        result
+    }
+}
+
+impl<K: Ord, V> CursorMut<'_, K, V> {
+    /// Insert key and value just after the cursor, or at the front from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the key the cursor is on and the next
+    pub fn insert_after(&mut self, key: K, value: V) {
+        let next = self.peek_next().map(|(next, _)| next);
+        assert!(cursor::between(self.key(), &key, next), "key must lie strictly between the keys around the cursor");
+        let index = self.index();
+        self.edit(index, |tree| tree.insert(key, value));
+    }
+
+    /// Insert key and value just before the cursor, or at the back from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the previous key and the key the cursor is on
+    pub fn insert_before(&mut self, key: K, value: V) {
+        let prev = self.peek_prev().map(|(prev, _)| prev);
+        assert!(cursor::between(prev, &key, self.key()), "key must lie strictly between the keys around the cursor");
+        let index = self.index().map(|index| index + 1);
+        self.edit(index, |tree| tree.insert(key, value));
+    }
+
+    /// Remove the pair the cursor is on and move to the next key
+    ///
+    /// Returns: the removed key and value, or None on the ghost
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let index = self.index()?;
+        Some(self.edit(Some(index), |tree| tree.remove_by(by_rank(index))))
+    }
+}
+
+impl<K, V> Drop for CursorMut<'_, K, V> {
+    fn drop(&mut self) {
+        self.tree.root = self.zipper.take();
+    }
+}
+
+// Returns a direction leading from the root to the node at zero based
+// position index in ascending key order
+fn by_rank<K, V>(mut index: usize) -> impl FnMut(&Node<K, V>) -> Ordering {
+    move |node| {
+        let left = node.left_sub.len();
+        let side = index.cmp(&left);
+        if side == Ordering::Greater {
+            index -= left + 1;
+        }
+        side
+    }
+}
+
+/// Iterator over borrowed keys of a Tree
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+/// Iterator over borrowed values of a Tree
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> IntoIterator for Tree<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        self.into_iter_in(Order::In)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Tree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Tree<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// Extending an empty tree with sorted input builds it in linear time; any
+// other input is inserted pair by pair, replacing the values of keys present
+impl<K: Ord, V> Extend<(K, V)> for Tree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let items: Vec<(K, V)> = iter.into_iter().collect();
+        if self.is_empty() && bulk::ascending(&items) {
+            *self = Tree::from_sorted(items);
+            return;
+        }
+        for (key, value) in items {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for Tree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Tree<K, V> {
+        let mut tree = Tree::default();
+        tree.extend(iter);
+        tree
     }
 }
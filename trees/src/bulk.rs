@@ -0,0 +1,36 @@
+// Building perfectly balanced trees from sorted input in linear time
+
+// Collect the (key, value) pairs of iter
+// Panics unless keys are strictly ascending
+pub(crate) fn sorted<K: Ord, V>(iter: impl IntoIterator<Item = (K, V)>) -> Vec<(K, V)> {
+    let items: Vec<(K, V)> = iter.into_iter().collect();
+    assert!(ascending(&items), "keys must be strictly ascending");
+    items
+}
+
+// Returns true if the keys of items are strictly ascending
+pub(crate) fn ascending<K: Ord, V>(items: &[(K, V)]) -> bool {
+    items.windows(2).all(|pair| pair[0].0 < pair[1].0)
+}
+
+// Returns the number of levels in a tree of n nodes built by build
+pub(crate) fn height(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+// Build a tree of the next n items in ascending key order, splitting them
+// evenly at every node so subtree sizes and heights differ by at most one.
+// join makes a node of an item and its left and right subtrees.
+pub(crate) fn build<I: Iterator, T>(
+    items: &mut I,
+    n: usize,
+    join: &mut impl FnMut(I::Item, Option<T>, Option<T>) -> T,
+) -> Option<T> {
+    if n == 0 {
+        return None;
+    }
+    let left = build(items, (n - 1) / 2, join);
+    let item = items.next().unwrap();
+    let right = build(items, n / 2, join);
+    Some(join(item, left, right))
+}
@@ -0,0 +1,246 @@
+// Zippers: a position in a tree kept as the path of nodes leading down to it,
+// so moving to a neighbouring key follows links from where the last move
+// ended instead of searching again from the root
+
+use std::{cmp::Ordering, ops::Deref};
+
+// A tree node with a subtree on either side (Less is left)
+pub(crate) trait Branch: Sized {
+    // Returns the root of the subtree on side, if any
+    fn subtree(&self, side: Ordering) -> Option<&Self>;
+
+    // Returns the link holding the subtree on side
+    fn link(&mut self, side: Ordering) -> &mut Option<Box<Self>>;
+}
+
+// A handle on a node that a zipper can hold. Owned nodes are detached from
+// their parents on the way down and reattached on the way up; shared ones
+// are only followed.
+pub(crate) trait Hold: Deref<Target: Branch> + Sized {
+    // Returns a handle on the child on side, detaching it if owned
+    fn detach(&mut self, side: Ordering) -> Option<Self>;
+
+    // Put back a child taken by detach
+    fn attach(&mut self, side: Ordering, child: Option<Self>);
+}
+
+impl<T: Branch> Hold for Box<T> {
+    fn detach(&mut self, side: Ordering) -> Option<Self> {
+        self.link(side).take()
+    }
+
+    fn attach(&mut self, side: Ordering, child: Option<Self>) {
+        *self.link(side) = child;
+    }
+}
+
+impl<T: Branch> Hold for &T {
+    fn detach(&mut self, side: Ordering) -> Option<Self> {
+        let node: &T = self;
+        node.subtree(side)
+    }
+
+    fn attach(&mut self, _: Ordering, _: Option<Self>) {}
+}
+
+// A position in a tree: on a node, or on the ghost position past both ends.
+// Walking every key takes a constant number of moves per key, so each move
+// takes amortized O(1).
+pub(crate) struct Zipper<H> {
+    // Ancestors of the focus from the root down, each with the side the path
+    // continues on, whose child on that side is detached if owned
+    path: Vec<(H, Ordering)>,
+    // The node the zipper is on, or the root of the whole tree on the ghost
+    focus: Option<H>,
+    ghost: bool,
+}
+
+impl<H: Hold> Zipper<H> {
+    // Returns a zipper on the ghost position of the tree under root
+    pub(crate) fn new(root: Option<H>) -> Zipper<H> {
+        Zipper { path: Vec::new(), focus: root, ghost: true }
+    }
+
+    // Returns true on the ghost position
+    pub(crate) fn is_ghost(&self) -> bool {
+        self.ghost
+    }
+
+    // Returns the node the zipper is on, None on the ghost
+    pub(crate) fn current(&self) -> Option<&H::Target> {
+        if self.ghost {
+            None
+        } else {
+            self.focus.as_deref()
+        }
+    }
+
+    // Returns the handle on the node the zipper is on, None on the ghost
+    pub(crate) fn current_mut(&mut self) -> Option<&mut H> {
+        if self.ghost {
+            None
+        } else {
+            self.focus.as_mut()
+        }
+    }
+
+    // Returns the ancestors of the current node from the root down, each with
+    // the side the path continues on
+    pub(crate) fn path(&self) -> impl Iterator<Item = (&H::Target, Ordering)> {
+        self.path.iter().map(|(node, side)| (&**node, *side))
+    }
+
+    // Returns the sides taken from the root down to the current node
+    pub(crate) fn sides(&self) -> Vec<Ordering> {
+        self.path.iter().map(|(_, side)| *side).collect()
+    }
+
+    // Go back up to the root and onto the ghost, reattaching every node
+    // Returns: the root of the tree, leaving the zipper empty
+    pub(crate) fn take(&mut self) -> Option<H> {
+        self.close();
+        self.focus.take()
+    }
+
+    // Returns the node next to the current one on side (Greater is next),
+    // from the ghost the node at the opposite end
+    pub(crate) fn neighbour(&self, side: Ordering) -> Option<&H::Target> {
+        let node = self.focus.as_deref()?;
+        let child = if self.ghost { Some(node) } else { node.subtree(side) };
+        if let Some(mut child) = child {
+            while let Some(next) = child.subtree(side.reverse()) {
+                child = next;
+            }
+            return Some(child);
+        }
+        // The nearest ancestor whose subtree on the other side holds the current node
+        self.path.iter().rev().find(|(_, from)| *from == side.reverse()).map(|(node, _)| &**node)
+    }
+
+    // Move to the node at the end of the tree on side (Less is the least key)
+    pub(crate) fn end(&mut self, side: Ordering) {
+        if self.enter() {
+            while self.descend(side) {}
+        }
+    }
+
+    // Move to the next node on side (Greater is next); from the ghost to the
+    // node at the opposite end, and past the last node onto the ghost
+    pub(crate) fn step(&mut self, side: Ordering) {
+        if self.ghost {
+            return self.end(side.reverse());
+        }
+        if self.descend(side) {
+            while self.descend(side.reverse()) {}
+            return;
+        }
+        // Climb until arriving from the other side of an ancestor, which comes next
+        loop {
+            match self.ascend() {
+                Some(from) if from != side => return,
+                Some(_) => {},
+                None => {
+                    self.ghost = true;
+                    return;
+                },
+            }
+        }
+    }
+
+    // Descend from the root as direction says (Equal to stop at a node),
+    // ending on the ghost if it leads off the tree
+    pub(crate) fn seek(&mut self, mut direction: impl FnMut(&H::Target) -> Ordering) {
+        if !self.enter() {
+            return;
+        }
+        loop {
+            let side = direction(self.focus.as_deref().unwrap());
+            if side == Ordering::Equal {
+                return;
+            }
+            if !self.descend(side) {
+                return self.close();
+            }
+        }
+    }
+
+    // Move to the first node in order for which below is false, where below
+    // holds for every node before it, or onto the ghost if there is none
+    pub(crate) fn lower_bound(&mut self, below: impl Fn(&H::Target) -> bool) {
+        if !self.enter() {
+            return;
+        }
+        // Depth of the last node passed that is not below
+        let mut found = None;
+        loop {
+            let side = if below(self.focus.as_deref().unwrap()) {
+                Ordering::Greater
+            } else {
+                found = Some(self.path.len());
+                Ordering::Less
+            };
+            if !self.descend(side) {
+                break;
+            }
+        }
+        match found {
+            Some(depth) => {
+                while self.path.len() > depth {
+                    self.ascend();
+                }
+            },
+            None => self.close(),
+        }
+    }
+
+    // Move from anywhere to the root
+    // Returns: false, staying on the ghost, if the tree is empty
+    fn enter(&mut self) -> bool {
+        self.close();
+        self.ghost = self.focus.is_none();
+        !self.ghost
+    }
+
+    // Go back up to the root and onto the ghost
+    fn close(&mut self) {
+        while self.ascend().is_some() {}
+        self.ghost = true;
+    }
+
+    // Move down to the child on side of the current node
+    // Returns: false, without moving, if there is no such child
+    fn descend(&mut self, side: Ordering) -> bool {
+        let Some(child) = self.focus.as_mut().unwrap().detach(side) else {
+            return false;
+        };
+        let parent = self.focus.replace(child).unwrap();
+        self.path.push((parent, side));
+        true
+    }
+
+    // Move up to the parent of the current node, reattaching it
+    // Returns: the side the current node hangs on, None at the root
+    fn ascend(&mut self) -> Option<Ordering> {
+        let (mut parent, side) = self.path.pop()?;
+        parent.attach(side, self.focus.take());
+        self.focus = Some(parent);
+        Some(side)
+    }
+}
+
+impl<H: Hold + Copy> Zipper<H> {
+    // Returns the handle on the node the zipper is on, None on the ghost
+    pub(crate) fn handle(&self) -> Option<H> {
+        if self.ghost {
+            None
+        } else {
+            self.focus
+        }
+    }
+}
+
+// Returns true if key lies strictly between lower and upper, either of which
+// may be missing
+pub(crate) fn between<K: Ord>(lower: Option<&K>, key: &K, upper: Option<&K>) -> bool {
+    lower.is_none_or(|lower| lower < key) && upper.is_none_or(|upper| key < upper)
+}
@@ -0,0 +1,278 @@
+//! Diagrams of trees for other tools: Graphviz DOT documents and standalone
+//! SVG images.
+//!
+//! Every tree writes itself with `to_dot(&Diagram)` and `to_svg(&Diagram)`. A
+//! [`Diagram`] can highlight the path a search took, as returned by `search`,
+//! and annotate nodes with the figures the text drawings of
+//! [`pretty`] use, such as AVL balance factors.
+//!
+//! The SVG layout follows Reingold and Tilford: each subtree is laid out on
+//! its own, then sibling subtrees are pushed together until their contours
+//! are a fixed distance apart, with the parent centred above them.
+use std::fmt::{Display, Write};
+
+use crate::pretty::{self, Annotation};
+use crate::traversal::{self, Shape};
+
+// Horizontal distance between neighbouring nodes, in layout units
+const SEPARATION: i64 = 2;
+// Pixel sizes of the SVG drawing
+const MARGIN: f64 = 16.0;
+const LEVEL: f64 = 64.0;
+const BOX_HEIGHT: f64 = 28.0;
+const CHAR_WIDTH: f64 = 9.0;
+const NOTE_CHAR_WIDTH: f64 = 7.0;
+const PADDING: f64 = 10.0;
+const GAP: f64 = 12.0;
+
+/// What a diagram shows beyond the keys and shape of a tree
+pub struct Diagram<'p, K> {
+    /// Keys visited by a search from the root, as returned by `search`;
+    /// their nodes and the edges between them are highlighted
+    pub path: &'p [&'p K],
+    /// Figure written beside each node, if any
+    pub annotation: Option<Annotation>,
+}
+
+impl<'p, K> Default for Diagram<'p, K> {
+    fn default() -> Diagram<'p, K> {
+        Diagram { path: &[], annotation: None }
+    }
+}
+
+// A node with the offsets of its subtrees, in layout units
+struct Item<'a, K> {
+    key: &'a K,
+    note: Option<String>,
+    left: Option<(usize, i64)>,
+    right: Option<(usize, i64)>,
+}
+
+// Layout of a subtree relative to its root: the leftmost and rightmost
+// offset at every level, root level first
+struct Subtree {
+    item: usize,
+    contour: Vec<(i64, i64)>,
+    height: usize,
+    size: usize,
+}
+
+// A node's final place in the diagram
+struct Spot {
+    item: usize,
+    parent: Option<usize>,
+    x: i64,
+    depth: usize,
+    highlighted: bool,
+}
+
+// Lays out the tree below root from the leaves up, then places every node top
+// down, returning the nodes and their places in level order
+fn arrange<'a, K, V, N>(root: Option<N>, diagram: &Diagram<'_, K>) -> (Vec<Item<'a, K>>, Vec<Spot>)
+where
+    K: PartialEq + 'a,
+    V: 'a,
+    N: Shape<Item = (&'a K, &'a V)>,
+{
+    let mut items: Vec<Item<K>> = Vec::new();
+    let top = traversal::fold(root, |node, left: Option<Subtree>, right: Option<Subtree>| {
+        let ((key, _), ..) = node.split();
+        let (left_dx, right_dx) = match (&left, &right) {
+            (Some(left), Some(right)) => {
+                let gap = left
+                    .contour
+                    .iter()
+                    .zip(&right.contour)
+                    .map(|(left, right)| left.1 - right.0 + SEPARATION)
+                    .fold(SEPARATION, i64::max);
+                // Keep the parent on a whole unit, centred above both children
+                let gap = gap + gap % 2;
+                (-gap / 2, gap / 2)
+            },
+            _ => (-1, 1),
+        };
+        let mut contour = vec![(0, 0)];
+        let (mut height, mut size) = (0, 1);
+        for (subtree, dx) in [(&left, left_dx), (&right, right_dx)] {
+            let Some(subtree) = subtree else {
+                continue;
+            };
+            for (level, &(low, high)) in subtree.contour.iter().enumerate() {
+                match contour.get_mut(level + 1) {
+                    Some(bounds) => *bounds = (bounds.0.min(low + dx), bounds.1.max(high + dx)),
+                    None => contour.push((low + dx, high + dx)),
+                }
+            }
+            height = height.max(subtree.height);
+            size += subtree.size;
+        }
+        let height_of = |subtree: &Option<Subtree>| subtree.as_ref().map_or(0, |subtree| subtree.height);
+        let note = diagram.annotation.map(|annotation| {
+            let balance = node.balance().unwrap_or(height_of(&right) as i32 - height_of(&left) as i32);
+            pretty::note(annotation, balance, size, height + 1)
+        });
+        items.push(Item {
+            key,
+            note,
+            left: left.map(|subtree| (subtree.item, left_dx)),
+            right: right.map(|subtree| (subtree.item, right_dx)),
+        });
+        Subtree { item: items.len() - 1, contour, height: height + 1, size }
+    });
+
+    let mut spots = Vec::new();
+    let Some(Subtree { item: top, .. }) = top else {
+        return (items, spots);
+    };
+    spots.push(Spot {
+        item: top,
+        parent: None,
+        x: 0,
+        depth: 0,
+        highlighted: diagram.path.first().is_some_and(|key| *key == items[top].key),
+    });
+    let mut next = 0;
+    while next < spots.len() {
+        let Spot { item, x, depth, highlighted, .. } = spots[next];
+        for (child, dx) in items[item].left.into_iter().chain(items[item].right) {
+            // A search path descends one level per key, so a child is on it
+            // only below a highlighted parent
+            let on_path = diagram.path.get(depth + 1).is_some_and(|key| *key == items[child].key);
+            spots.push(Spot {
+                item: child,
+                parent: Some(next),
+                x: x + dx,
+                depth: depth + 1,
+                highlighted: highlighted && on_path,
+            });
+        }
+        next += 1;
+    }
+    (items, spots)
+}
+
+// Writes the tree below root as a Graphviz DOT document. A child without a
+// sibling gets an invisible one so Graphviz keeps it on its own side.
+pub(crate) fn dot<'a, K, V, N>(root: Option<N>, diagram: &Diagram<'_, K>) -> String
+where
+    K: Display + PartialEq + 'a,
+    V: 'a,
+    N: Shape<Item = (&'a K, &'a V)>,
+{
+    let (items, spots) = arrange(root, diagram);
+    let mut out = String::from("digraph tree {\n    node [shape=box, style=rounded, fontname=\"monospace\"];\n");
+    for spot in &spots {
+        let item = &items[spot.item];
+        let _ = write!(out, "    n{} [label=\"{}\"", spot.item, escape_dot(&item.key.to_string()));
+        if let Some(note) = &item.note {
+            let _ = write!(out, ", xlabel=\"{}\"", escape_dot(note));
+        }
+        if spot.highlighted {
+            out.push_str(", color=red, penwidth=2");
+        }
+        out.push_str("];\n");
+    }
+    // Edges in level order, so each parent's edges are listed left to right
+    for spot in &spots {
+        let Some(parent) = spot.parent else {
+            continue;
+        };
+        let id = spots[parent].item;
+        let parent = &items[id];
+        if parent.left.is_none() {
+            let _ = writeln!(out, "    n{id}l [style=invis, label=\"\"];\n    n{id} -> n{id}l [style=invis];");
+        }
+        let _ = write!(out, "    n{id} -> n{}", spot.item);
+        if spot.highlighted {
+            out.push_str(" [color=red, penwidth=2]");
+        }
+        out.push_str(";\n");
+        if parent.right.is_none() {
+            let _ = writeln!(out, "    n{id}r [style=invis, label=\"\"];\n    n{id} -> n{id}r [style=invis];");
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Writes the tree below root as a standalone SVG image
+pub(crate) fn svg<'a, K, V, N>(root: Option<N>, diagram: &Diagram<'_, K>) -> String
+where
+    K: Display + PartialEq + 'a,
+    V: 'a,
+    N: Shape<Item = (&'a K, &'a V)>,
+{
+    let (items, spots) = arrange(root, diagram);
+    let labels: Vec<String> = items.iter().map(|item| item.key.to_string()).collect();
+    let box_width = |item: usize| labels[item].chars().count() as f64 * CHAR_WIDTH + 2.0 * PADDING;
+    let note_width =
+        |item: usize| items[item].note.as_ref().map_or(0.0, |note| note.chars().count() as f64 * NOTE_CHAR_WIDTH + 4.0);
+    // A unit wide enough that the widest node and its note never meet a neighbour
+    let widest = spots.iter().map(|spot| box_width(spot.item) + 2.0 * note_width(spot.item)).fold(0.0, f64::max);
+    let unit = (widest + GAP) / SEPARATION as f64;
+    let min_x = spots.iter().map(|spot| spot.x).min().unwrap_or(0);
+    let max_x = spots.iter().map(|spot| spot.x).max().unwrap_or(0);
+    let depth = spots.iter().map(|spot| spot.depth).max().unwrap_or(0);
+    let centre = |spot: &Spot| {
+        (MARGIN + widest / 2.0 + (spot.x - min_x) as f64 * unit, MARGIN + BOX_HEIGHT / 2.0 + spot.depth as f64 * LEVEL)
+    };
+    let (width, height) = if spots.is_empty() {
+        (2.0 * MARGIN, 2.0 * MARGIN)
+    } else {
+        (2.0 * MARGIN + widest + (max_x - min_x) as f64 * unit, 2.0 * MARGIN + BOX_HEIGHT + depth as f64 * LEVEL)
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"14\">"
+    );
+    // Edges first so nodes are drawn over their ends
+    for spot in &spots {
+        if let Some(parent) = spot.parent {
+            let ((x1, y1), (x2, y2)) = (centre(&spots[parent]), centre(spot));
+            let (stroke, stroke_width) = if spot.highlighted { ("#d62728", 3) } else { ("#555555", 1) };
+            let _ = writeln!(
+                out,
+                "  <line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"/>"
+            );
+        }
+    }
+    for spot in &spots {
+        let (x, y) = centre(spot);
+        let w = box_width(spot.item);
+        let (stroke, fill) = if spot.highlighted { ("#d62728", "#fde2e1") } else { ("#333333", "#ffffff") };
+        let _ = writeln!(
+            out,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{w}\" height=\"{BOX_HEIGHT}\" rx=\"{}\" fill=\"{fill}\" stroke=\"{stroke}\"/>",
+            x - w / 2.0,
+            y - BOX_HEIGHT / 2.0,
+            BOX_HEIGHT / 2.0
+        );
+        let _ = writeln!(
+            out,
+            "  <text x=\"{x}\" y=\"{y}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+            escape_xml(&labels[spot.item])
+        );
+        if let Some(note) = &items[spot.item].note {
+            let _ = writeln!(
+                out,
+                "  <text x=\"{}\" y=\"{}\" font-size=\"11\" fill=\"#1f6fb2\">{}</text>",
+                x + w / 2.0 + 2.0,
+                y - BOX_HEIGHT / 2.0,
+                escape_xml(note)
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -1,3 +1,45 @@
-mod binary_search_tree;
-mod avl_tree;
-mod scapegoat_tree;
\ No newline at end of file
+//! Ordered maps backed by binary search trees.
+//!
+//! Three tree kinds share one API through the [`OrderedMap`] trait:
+//!
+//! - [`BinarySearchTree`]: a plain, unbalanced binary search tree
+//! - [`AvlTree`]: an AVL tree, rebalanced by rotations on every insert and delete
+//! - [`SGTree`]: a scapegoat tree, rebalanced by rebuilding subtrees, whose
+//!   nodes carry no balance metadata beyond their subtree sizes
+//!
+//! [`AvlMultiMap`] is an AVL tree that keeps every value inserted under a key
+//! instead of one per key.
+//!
+//! Every tree can be walked in any traversal [`Order`], queried by key range,
+//! and asked for order statistics or nearest keys. Cursors step through a tree
+//! in key order and edit it in place, without searching from the root at every
+//! step. Trees display as drawings of their shape, styled through the
+//! [`pretty`] module, and export Graphviz and SVG diagrams through the
+//! [`export`] module. Each checks its own invariants with `validate()`, see the
+//! [`validate`] module, and, when built with the `stats` feature, counts its
+//! rebalancing work in the [`stats`] module. The [`repl`] module drives any of
+//! them from text commands, as the `avl_tree` and `binary_search_tree` binaries
+//! do; the `bench` binary compares them across workloads.
+#![forbid(unsafe_code)]
+#![warn(missing_docs)]
+
+pub mod avl_multimap;
+pub mod avl_tree;
+pub mod binary_search_tree;
+mod bulk;
+mod cursor;
+pub mod export;
+mod ordered_map;
+pub mod pretty;
+pub mod repl;
+pub mod scapegoat_tree;
+pub mod stats;
+mod traversal;
+pub mod validate;
+
+pub use avl_multimap::MultiMap as AvlMultiMap;
+pub use avl_tree::Tree as AvlTree;
+pub use binary_search_tree::Tree as BinarySearchTree;
+pub use ordered_map::OrderedMap;
+pub use scapegoat_tree::SGTree;
+pub use traversal::Order;
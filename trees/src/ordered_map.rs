@@ -0,0 +1,260 @@
+use std::{borrow::Borrow, ops::RangeBounds};
+
+use crate::{avl_tree, binary_search_tree, scapegoat_tree};
+
+/// Operations shared by every tree in the crate, so that code can be generic
+/// over the kind of tree and switch kinds with a type parameter
+pub trait OrderedMap<K: Ord, V> {
+    /// Iterator returned by iter
+    type Iter<'a>: Iterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+    /// Double ended iterator returned by range
+    type Range<'a>: DoubleEndedIterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    /// Insert value under key
+    ///
+    /// Returns: the value previously stored under key, which is replaced, or None
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Remove key from the map
+    ///
+    /// Returns: the removed key and value, or None if key was not found
+    fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    /// Remove the least key from the map
+    ///
+    /// Returns: the removed key and value, or None on empty map
+    fn extract_min(&mut self) -> Option<(K, V)>;
+
+    /// Remove the greatest key from the map
+    ///
+    /// Returns: the removed key and value, or None on empty map
+    fn extract_max(&mut self) -> Option<(K, V)>;
+
+    /// Returns a reference to the value stored under key, if any
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    /// Iterate in ascending key order over the (key, value) pairs whose keys lie in range
+    fn range<Q, R>(&self, range: R) -> Self::Range<'_>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>;
+
+    /// Iterate over (key, value) pairs in ascending key order
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// Returns the number of keys in the map
+    fn len(&self) -> usize;
+
+    /// Returns true if the map holds no keys
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if key is present in the map
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the (key, value) pair with the least key, or None on empty map
+    fn min(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Returns the (key, value) pair with the greatest key, or None on empty map
+    fn max(&self) -> Option<(&K, &V)> {
+        self.range::<K, _>(..).next_back()
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for binary_search_tree::Tree<K, V> {
+    type Iter<'a>
+        = binary_search_tree::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type Range<'a>
+        = binary_search_tree::Range<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove(key)
+    }
+
+    fn extract_min(&mut self) -> Option<(K, V)> {
+        self.extract_min()
+    }
+
+    fn extract_max(&mut self) -> Option<(K, V)> {
+        self.extract_max()
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key)
+    }
+
+    fn range<Q, R>(&self, range: R) -> Self::Range<'_>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.range(range)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for avl_tree::Tree<K, V> {
+    type Iter<'a>
+        = avl_tree::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type Range<'a>
+        = avl_tree::Range<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.delete(key)
+    }
+
+    fn extract_min(&mut self) -> Option<(K, V)> {
+        self.extract_min()
+    }
+
+    fn extract_max(&mut self) -> Option<(K, V)> {
+        self.extract_max()
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key)
+    }
+
+    fn range<Q, R>(&self, range: R) -> Self::Range<'_>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.range(range)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for scapegoat_tree::SGTree<K, V> {
+    type Iter<'a>
+        = scapegoat_tree::Iter<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+    type Range<'a>
+        = scapegoat_tree::Range<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert(key, value)
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.delete(key)
+    }
+
+    fn extract_min(&mut self) -> Option<(K, V)> {
+        self.extract_min()
+    }
+
+    fn extract_max(&mut self) -> Option<(K, V)> {
+        self.extract_max()
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key)
+    }
+
+    fn range<Q, R>(&self, range: R) -> Self::Range<'_>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.range(range)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
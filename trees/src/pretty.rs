@@ -0,0 +1,252 @@
+//! Text drawings of tree structure.
+//!
+//! Every tree renders itself with `render(&Style)`, and its `Display` uses the
+//! default [`Style`]. Each node's key is drawn centred above a connector line
+//! joining it to its children:
+//!
+//! ```text
+//!      4
+//!   ┌──┴──┐
+//!   2     6
+//! ┌─┴─┐ ┌─┴─┐
+//! 1   3 5   7
+//! ```
+use std::fmt::Display;
+
+use crate::traversal::{self, Shape};
+
+/// Extra figure drawn after each node's key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    /// Height of the right subtree minus height of the left subtree, as stored
+    /// by AVL nodes and computed for other trees, drawn as `4(+1)` or `4(0)`
+    Balance,
+    /// Number of nodes in the subtree, drawn as `4(n=3)`
+    Size,
+    /// Number of levels in the subtree, drawn as `4(h=2)`
+    Height,
+}
+
+/// Options for drawing a tree
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    /// Figure drawn after each key, if any
+    pub annotation: Option<Annotation>,
+    /// Column width to wrap wide drawings to; a drawing wider than this is
+    /// cut into vertical strips printed one below the other
+    pub width: Option<usize>,
+    /// Draw connectors with `+`, `-` instead of box-drawing characters
+    pub ascii: bool,
+}
+
+// Pieces of the connector line below a node
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Glyph {
+    // Down to the left child
+    LeftArm,
+    // Down to the right child
+    RightArm,
+    Across,
+    // Up to a node with two children
+    Fork,
+    // Up to a node with only a left child
+    LeftEnd,
+    // Up to a node with only a right child
+    RightEnd,
+}
+
+impl Glyph {
+    fn draw(self, ascii: bool) -> char {
+        match (self, ascii) {
+            (Glyph::Across, true) => '-',
+            (_, true) => '+',
+            (Glyph::LeftArm, false) => '┌',
+            (Glyph::RightArm, false) => '┐',
+            (Glyph::Across, false) => '─',
+            (Glyph::Fork, false) => '┴',
+            (Glyph::LeftEnd, false) => '┘',
+            (Glyph::RightEnd, false) => '└',
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Blank,
+    Text(char),
+    Line(Glyph),
+}
+
+// Geometry of the drawing of one subtree, relative to its top left corner
+struct Block {
+    label: Vec<char>,
+    width: usize,
+    // Column the node's label is centred on and its parent's arm lands on
+    root: usize,
+    // Index into the finished blocks and column offset of each subtree
+    left: Option<(usize, usize)>,
+    right: Option<(usize, usize)>,
+    height: usize,
+    size: usize,
+}
+
+impl Block {
+    // Returns the geometry of a node labelled label above the finished
+    // subtrees left and right, given as indices into blocks
+    fn join(label: Vec<char>, left: Option<usize>, right: Option<usize>, blocks: &[Block]) -> Block {
+        let centre = label.len().saturating_sub(1) / 2;
+        let (left_block, right_block) = (left.map(|i| &blocks[i]), right.map(|i| &blocks[i]));
+        // Offsets of the subtrees and column of the node, chosen so each arm
+        // has at least one Across between it and the node
+        let (mut left_x, mut right_x, mut root) = (0, 0, centre);
+        match (left_block, right_block) {
+            (Some(left), Some(right)) => {
+                right_x = (left.width + 1).max((left.root + 4).saturating_sub(right.root));
+                root = (left.root + right_x + right.root) / 2;
+            },
+            (Some(left), None) => root = left.root + 2,
+            (None, Some(right)) => {
+                right_x = 2usize.saturating_sub(right.root);
+                root = right_x + right.root - 2;
+            },
+            (None, None) => {},
+        }
+        // Shift everything right if the label would stick out on the left
+        let shift = centre.saturating_sub(root);
+        left_x += shift;
+        right_x += shift;
+        root += shift;
+
+        let mut width = root - centre + label.len();
+        let (mut height, mut size) = (0, 1);
+        for (block, x) in [(left_block, left_x), (right_block, right_x)] {
+            if let Some(block) = block {
+                width = width.max(x + block.width);
+                height = height.max(block.height);
+                size += block.size;
+            }
+        }
+        Block {
+            label,
+            width,
+            root,
+            left: left.map(|i| (i, left_x)),
+            right: right.map(|i| (i, right_x)),
+            height: height + 1,
+            size,
+        }
+    }
+}
+
+// Draws the tree below root. Subtree geometry is worked out from the leaves
+// up, then every label and connector is written once into a single grid.
+pub(crate) fn render<'a, K: Display + 'a, V: 'a, N>(root: Option<N>, style: &Style) -> String
+where
+    N: Shape<Item = (&'a K, &'a V)>,
+{
+    let mut blocks: Vec<Block> = Vec::new();
+    let top = traversal::fold(root, |node, left, right| {
+        let ((key, _), ..) = node.split();
+        let (left_block, right_block) = (left.map(|i| &blocks[i]), right.map(|i| &blocks[i]));
+        let mut label = key.to_string();
+        if let Some(annotation) = style.annotation {
+            let height = |block: Option<&Block>| block.map_or(0, |block| block.height);
+            let size = |block: Option<&Block>| block.map_or(0, |block| block.size);
+            let balance = node.balance().unwrap_or(height(right_block) as i32 - height(left_block) as i32);
+            let height = 1 + height(left_block).max(height(right_block));
+            let size = 1 + size(left_block) + size(right_block);
+            label = format!("{label}({})", note(annotation, balance, size, height));
+        }
+        let block = Block::join(label.chars().collect(), left, right, &blocks);
+        blocks.push(block);
+        blocks.len() - 1
+    });
+    let Some(top) = top else {
+        return String::from("(empty)");
+    };
+
+    let mut grid = vec![vec![Cell::Blank; blocks[top].width]; 2 * blocks[top].height - 1];
+    // (block, column of its left edge, row of its label)
+    let mut placements = vec![(top, 0, 0)];
+    while let Some((i, x, y)) = placements.pop() {
+        let block = &blocks[i];
+        let root = x + block.root;
+        let start = root - block.label.len().saturating_sub(1) / 2;
+        for (offset, c) in block.label.iter().enumerate() {
+            grid[y][start + offset] = Cell::Text(*c);
+        }
+        let arms =
+            (block.left.map(|(j, dx)| x + dx + blocks[j].root), block.right.map(|(j, dx)| x + dx + blocks[j].root));
+        if arms == (None, None) {
+            continue;
+        }
+        let line = &mut grid[y + 1];
+        line[arms.0.unwrap_or(root)..=arms.1.unwrap_or(root)].fill(Cell::Line(Glyph::Across));
+        line[root] = Cell::Line(match arms {
+            (Some(_), Some(_)) => Glyph::Fork,
+            (Some(_), None) => Glyph::LeftEnd,
+            _ => Glyph::RightEnd,
+        });
+        if let Some(arm) = arms.0 {
+            line[arm] = Cell::Line(Glyph::LeftArm);
+        }
+        if let Some(arm) = arms.1 {
+            line[arm] = Cell::Line(Glyph::RightArm);
+        }
+        for (j, dx) in block.left.into_iter().chain(block.right) {
+            placements.push((j, x + dx, y + 2));
+        }
+    }
+    strips(&grid, style)
+}
+
+// Returns the figure annotation asks for, out of a node's balance factor
+// and its subtree's size and height
+pub(crate) fn note(annotation: Annotation, balance: i32, size: usize, height: usize) -> String {
+    match annotation {
+        Annotation::Balance if balance > 0 => format!("+{balance}"),
+        Annotation::Balance => balance.to_string(),
+        Annotation::Size => format!("n={size}"),
+        Annotation::Height => format!("h={height}"),
+    }
+}
+
+// Cuts the drawing into strips no wider than the style's width, preferring
+// cuts that split no label, and returns them one below the other with the
+// blank lines around each strip dropped
+fn strips(grid: &[Vec<Cell>], style: &Style) -> String {
+    let total = grid[0].len();
+    let width = style.width.unwrap_or(total).max(1);
+    // Returns true if cutting before column splits the text of a label
+    let splits =
+        |column: usize| grid.iter().any(|row| matches!((row[column - 1], row[column]), (Cell::Text(_), Cell::Text(_))));
+    let mut strips = Vec::new();
+    let mut start = 0;
+    while start < total {
+        let mut end = (start + width).min(total);
+        if end < total {
+            end = (start + 1..=end).rev().find(|&column| !splits(column)).unwrap_or(end);
+        }
+        let mut lines: Vec<String> = grid
+            .iter()
+            .map(|row| {
+                let line: String = row[start..end]
+                    .iter()
+                    .map(|cell| match cell {
+                        Cell::Blank => ' ',
+                        Cell::Text(c) => *c,
+                        Cell::Line(glyph) => glyph.draw(style.ascii),
+                    })
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let blank = lines.iter().take_while(|line| line.is_empty()).count();
+        strips.push(lines[blank..].join("\n"));
+        start = end;
+    }
+    strips.join("\n\n")
+}
@@ -0,0 +1,467 @@
+//! Line based command interpreter over the trees of this crate.
+//!
+//! Each line holds one command followed by its arguments, separated by
+//! whitespace. Blank lines and lines starting with `#` are ignored, so a file
+//! of commands can be replayed as a script. Keys are signed integers.
+//!
+//! Commands (`help` prints the same list):
+//!
+//! - `insert <key>...`, `delete <key>...`: insert or delete each key
+//! - `search <key>`: print the keys visited on the way to key
+//! - `inorder`, `preorder`, `postorder`, `bft`: print the keys in that order
+//! - `height`, `len`: print the number of levels or keys
+//! - `show [balance|size|height]`: draw the tree, annotating each key if asked
+//! - `validate`: check the tree's invariants, printing each violation
+//! - `dot <path> [key]`, `svg <path> [key]`: write a Graphviz or SVG diagram,
+//!   highlighting the search path to key
+//! - `backend [bst|avl|sg [a b]]`: print or switch the backend, keeping the keys
+//! - `clear`: remove every key
+//! - `load <path>`, `save <path>`: run a script, or write the commands run so far as one
+//! - `help`, `quit`, `exit`
+use std::{
+    env, fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    export::Diagram,
+    pretty::{Annotation, Style},
+    scapegoat_tree,
+    validate::Violation,
+    AvlTree, BinarySearchTree, Order, SGTree,
+};
+
+// Columns show wraps drawings to
+const WIDTH: usize = 80;
+
+const HELP: &str = "\
+insert <key>...        insert each key
+delete <key>...        delete each key
+search <key>           print the keys visited on the way to key
+inorder                print the keys in ascending order
+preorder               print the keys in pre-order
+postorder              print the keys in post-order
+bft                    print the keys breadth first
+height                 print the number of levels
+len                    print the number of keys
+show [balance|size|height]
+                       draw the tree, annotating each key if asked
+validate               check the tree's invariants, printing each violation
+dot <path> [key]       write a Graphviz diagram, highlighting the search for key
+svg <path> [key]       write an SVG diagram, highlighting the search for key
+backend [bst|avl|sg [a b]]
+                       print or switch the backend, keeping the keys
+clear                  remove every key
+load <path>            run the commands in a script
+save <path>            write the commands run so far as a script
+help                   print this list
+quit | exit            stop";
+
+/// Tree implementation a [`Repl`] runs its commands against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Unbalanced [`BinarySearchTree`]
+    Bst,
+    /// [`AvlTree`]
+    Avl,
+    /// [`SGTree`] with α = a / b
+    Scapegoat(u32, u32),
+}
+
+enum Backend {
+    Bst(BinarySearchTree<i64, ()>),
+    Avl(AvlTree<i64, ()>),
+    Scapegoat(SGTree<i64, ()>),
+}
+
+impl Backend {
+    fn new(kind: Kind) -> Backend {
+        match kind {
+            Kind::Bst => Backend::Bst(BinarySearchTree::default()),
+            Kind::Avl => Backend::Avl(AvlTree::default()),
+            Kind::Scapegoat(a, b) => Backend::Scapegoat(SGTree::new(a, b, None)),
+        }
+    }
+
+    fn kind(&self) -> Kind {
+        match self {
+            Backend::Bst(_) => Kind::Bst,
+            Backend::Avl(_) => Kind::Avl,
+            Backend::Scapegoat(tree) => {
+                let (a, b) = tree.alpha();
+                Kind::Scapegoat(a, b)
+            },
+        }
+    }
+
+    // Returns: true if key was inserted, false if it was present already
+    fn insert(&mut self, key: i64) -> bool {
+        match self {
+            Backend::Bst(tree) => tree.insert(key, ()).is_none(),
+            Backend::Avl(tree) => tree.insert(key, ()).is_none(),
+            Backend::Scapegoat(tree) => tree.insert(key, ()).is_none(),
+        }
+    }
+
+    // Returns: true if key was deleted, false if it was not found
+    fn delete(&mut self, key: i64) -> bool {
+        match self {
+            Backend::Bst(tree) => tree.delete(&key),
+            Backend::Avl(tree) => tree.delete(&key).is_some(),
+            Backend::Scapegoat(tree) => tree.delete(&key).is_some(),
+        }
+    }
+
+    fn search(&self, key: i64) -> Vec<i64> {
+        let path = match self {
+            Backend::Bst(tree) => tree.search(&key),
+            Backend::Avl(tree) => tree.search(&key),
+            Backend::Scapegoat(tree) => tree.search(&key),
+        };
+        path.into_iter().copied().collect()
+    }
+
+    fn keys(&self, order: Order) -> Vec<i64> {
+        match self {
+            Backend::Bst(tree) => tree.keys_in(order).copied().collect(),
+            Backend::Avl(tree) => tree.keys_in(order).copied().collect(),
+            Backend::Scapegoat(tree) => tree.keys_in(order).copied().collect(),
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Backend::Bst(tree) => tree.height(),
+            Backend::Avl(tree) => tree.height(),
+            Backend::Scapegoat(tree) => tree.height(),
+        }
+    }
+
+    // Returns the tree as a DOT document or SVG image, highlighting the
+    // search path to key if given and annotating AVL balance factors
+    fn export(&self, svg: bool, key: Option<i64>) -> String {
+        let key = key.as_ref();
+        match self {
+            Backend::Bst(tree) => {
+                let path = key.map(|key| tree.search(key)).unwrap_or_default();
+                let diagram = Diagram { path: &path, annotation: None };
+                if svg {
+                    tree.to_svg(&diagram)
+                } else {
+                    tree.to_dot(&diagram)
+                }
+            },
+            Backend::Avl(tree) => {
+                let path = key.map(|key| tree.search(key)).unwrap_or_default();
+                let diagram = Diagram { path: &path, annotation: Some(Annotation::Balance) };
+                if svg {
+                    tree.to_svg(&diagram)
+                } else {
+                    tree.to_dot(&diagram)
+                }
+            },
+            Backend::Scapegoat(tree) => {
+                let path = key.map(|key| tree.search(key)).unwrap_or_default();
+                let diagram = Diagram { path: &path, annotation: None };
+                if svg {
+                    tree.to_svg(&diagram)
+                } else {
+                    tree.to_dot(&diagram)
+                }
+            },
+        }
+    }
+
+    // Returns: one line per violation of the tree's invariants
+    fn validate(&self) -> Vec<String> {
+        let lines =
+            |violations: Vec<Violation<i64>>| violations.iter().map(|violation| violation.to_string()).collect();
+        match self {
+            Backend::Bst(tree) => tree.validate().err().map_or_else(Vec::new, lines),
+            Backend::Avl(tree) => tree.validate().err().map_or_else(Vec::new, lines),
+            Backend::Scapegoat(tree) => tree.validate().err().map_or_else(Vec::new, lines),
+        }
+    }
+
+    fn render(&self, style: &Style) -> String {
+        match self {
+            Backend::Bst(tree) => tree.render(style),
+            Backend::Avl(tree) => tree.render(style),
+            Backend::Scapegoat(tree) => tree.render(style),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Backend::Bst(tree) => tree.len(),
+            Backend::Avl(tree) => tree.len(),
+            Backend::Scapegoat(tree) => tree.len(),
+        }
+    }
+}
+
+/// What the caller of [`Repl::execute`] should do after a command
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Print the text (possibly empty) and read the next command
+    Continue(String),
+    /// Print the text (possibly empty) and stop reading commands
+    Quit(String),
+}
+
+/// Command interpreter holding one tree of i64 keys and the commands run on it
+pub struct Repl {
+    tree: Backend,
+    history: Vec<String>,
+    // Scripts being run, outermost first, so one loading itself is caught
+    loading: Vec<PathBuf>,
+}
+
+impl Repl {
+    /// Returns a new interpreter over an empty tree of the given kind
+    pub fn new(kind: Kind) -> Repl {
+        Repl { tree: Backend::new(kind), history: Vec::new(), loading: Vec::new() }
+    }
+
+    /// Returns the kind of tree commands currently run against
+    pub fn kind(&self) -> Kind {
+        self.tree.kind()
+    }
+
+    /// Run a single command line
+    ///
+    /// Returns: the text to print and whether to go on, or a message
+    /// describing why the command was rejected
+    pub fn execute(&mut self, line: &str) -> Result<Step, String> {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            None => return Ok(Step::Continue(String::new())),
+            Some(word) if word.starts_with('#') => return Ok(Step::Continue(String::new())),
+            Some(word) => word,
+        };
+        let args: Vec<&str> = words.collect();
+        let mut out = String::new();
+        match (command, args.as_slice()) {
+            ("quit" | "exit", []) => return Ok(Step::Quit(out)),
+            ("help", []) => out.push_str(HELP),
+            ("load", [path]) => return self.load(path),
+            ("save", [path]) => {
+                let script: String = self.history.iter().map(|line| format!("{line}\n")).collect();
+                fs::write(path, script).map_err(|err| format!("cannot write {path}: {err}"))?;
+                out = format!("saved {} commands to {path}", self.history.len());
+            },
+            ("insert", [_, ..]) => {
+                for key in parse_keys(&args)? {
+                    let verb = if self.tree.insert(key) { "inserted" } else { "already present:" };
+                    push_line(&mut out, &format!("{verb} {key}"));
+                }
+            },
+            ("delete", [_, ..]) => {
+                for key in parse_keys(&args)? {
+                    let verb = if self.tree.delete(key) { "deleted" } else { "not found:" };
+                    push_line(&mut out, &format!("{verb} {key}"));
+                }
+            },
+            ("search", [key]) => {
+                let key = parse_key(key)?;
+                let path = self.tree.search(key);
+                out = if path.is_empty() { format!("not found: {key}") } else { format!("found {key} via {path:?}") };
+            },
+            ("inorder", []) => out = format!("{:?}", self.tree.keys(Order::In)),
+            ("preorder", []) => out = format!("{:?}", self.tree.keys(Order::Pre)),
+            ("postorder", []) => out = format!("{:?}", self.tree.keys(Order::Post)),
+            ("bft", []) => out = format!("{:?}", self.tree.keys(Order::Level)),
+            ("height", []) => out = self.tree.height().to_string(),
+            ("len", []) => out = self.tree.len().to_string(),
+            ("show", []) => out = self.show(None),
+            ("show", [annotation]) => out = self.show(Some(parse_annotation(annotation)?)),
+            ("validate", []) => {
+                let violations = self.tree.validate();
+                out = if violations.is_empty() { "ok".to_string() } else { violations.join("\n") };
+            },
+            ("dot" | "svg", [path, key @ ..]) if key.len() <= 1 => {
+                let key = key.first().map(|key| parse_key(key)).transpose()?;
+                let document = self.tree.export(command == "svg", key);
+                fs::write(path, document).map_err(|err| format!("cannot write {path}: {err}"))?;
+                out = format!("wrote {path}");
+            },
+            ("backend", []) => out = describe(self.kind()),
+            ("backend", [name, alpha @ ..]) => {
+                let kind = parse_kind(name, alpha)?;
+                self.switch(kind);
+                out = format!("switched to {}", describe(kind));
+            },
+            ("clear", []) => self.tree = Backend::new(self.kind()),
+            (
+                "quit" | "exit" | "help" | "load" | "save" | "insert" | "delete" | "search" | "inorder" | "preorder"
+                | "postorder" | "bft" | "height" | "len" | "show" | "validate" | "dot" | "svg" | "clear",
+                _,
+            ) => return Err(format!("wrong arguments for {command}, see help")),
+            _ => return Err(format!("unknown command {command}, see help")),
+        }
+        // A saved script replays what changed or was shown, not how it was saved
+        if !matches!(command, "save" | "help") {
+            self.history.push(line.to_string());
+        }
+        Ok(Step::Continue(out))
+    }
+
+    /// Run every command of the script at path, stopping at the first rejected one
+    /// A script may load others, but not one it is itself loaded from
+    ///
+    /// Returns: the text printed by the script's commands, or the rejected
+    /// command's message prefixed by its location
+    pub fn load(&mut self, path: &str) -> Result<Step, String> {
+        let script = fs::read_to_string(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        // The same script may be named by different paths
+        let canonical = fs::canonicalize(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        if self.loading.contains(&canonical) {
+            return Err(format!("{path} is already being loaded"));
+        }
+        self.loading.push(canonical);
+        let step = self.replay(path, &script);
+        self.loading.pop();
+        step
+    }
+
+    // Run every line of script, which was read from path
+    fn replay(&mut self, path: &str, script: &str) -> Result<Step, String> {
+        let mut out = String::new();
+        for (number, line) in script.lines().enumerate() {
+            let step = self.execute(line).map_err(|message| format!("{path}:{}: {message}", number + 1))?;
+            match step {
+                Step::Continue(text) => push_line(&mut out, &text),
+                Step::Quit(text) => {
+                    push_line(&mut out, &text);
+                    return Ok(Step::Quit(out));
+                },
+            }
+        }
+        Ok(Step::Continue(out))
+    }
+
+    /// Read commands from input until it ends or a command quits, writing
+    /// results and messages to output
+    /// Writes a "> " prompt before each command if prompt is true
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write, prompt: bool) -> io::Result<()> {
+        let mut lines = input.lines();
+        loop {
+            if prompt {
+                write!(output, "> ")?;
+                output.flush()?;
+            }
+            let Some(line) = lines.next() else {
+                return Ok(());
+            };
+            match self.execute(&line?) {
+                Ok(Step::Continue(text)) => print_text(&mut output, &text)?,
+                Ok(Step::Quit(text)) => return print_text(&mut output, &text),
+                Err(message) => writeln!(output, "error: {message}")?,
+            }
+        }
+    }
+
+    // Move every key into a fresh tree of kind, in level order so a balanced
+    // tree stays balanced when copied into an unbalanced one
+    fn switch(&mut self, kind: Kind) {
+        let keys = self.tree.keys(Order::Level);
+        self.tree = Backend::new(kind);
+        for key in keys {
+            self.tree.insert(key);
+        }
+    }
+
+    fn show(&self, annotation: Option<Annotation>) -> String {
+        let style = Style { annotation, width: Some(WIDTH), ..Style::default() };
+        format!(
+            "{}: {} keys, height {}\n{}",
+            describe(self.kind()),
+            self.tree.len(),
+            self.tree.height(),
+            self.tree.render(&style)
+        )
+    }
+}
+
+/// Run an interpreter over a tree of kind as a program: replay each script
+/// named on the command line, then read commands from standard input,
+/// prompting for them if it is a terminal
+pub fn run(kind: Kind) -> io::Result<()> {
+    let mut repl = Repl::new(kind);
+    for path in env::args().skip(1) {
+        match repl.load(&path) {
+            Ok(Step::Continue(text)) => print_text(&mut io::stdout(), &text)?,
+            Ok(Step::Quit(text)) => return print_text(&mut io::stdout(), &text),
+            Err(message) => eprintln!("error: {message}"),
+        }
+    }
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    repl.run(stdin.lock(), io::stdout(), prompt)
+}
+
+fn describe(kind: Kind) -> String {
+    match kind {
+        Kind::Bst => "bst".to_string(),
+        Kind::Avl => "avl".to_string(),
+        Kind::Scapegoat(a, b) => format!("sg {a} {b}"),
+    }
+}
+
+/// Returns the kind of tree named bst, avl or sg, a scapegoat tree taking α
+/// as numerator and denominator if given, else α = 2/3
+pub fn parse_kind(name: &str, alpha: &[&str]) -> Result<Kind, String> {
+    match (name, alpha) {
+        ("bst", []) => Ok(Kind::Bst),
+        ("avl", []) => Ok(Kind::Avl),
+        ("sg", []) => Ok(Kind::Scapegoat(2, 3)),
+        ("sg", [a, b]) => {
+            let a: u32 = a.parse().map_err(|_| format!("invalid alpha numerator {a}"))?;
+            let b: u32 = b.parse().map_err(|_| format!("invalid alpha denominator {b}"))?;
+            if scapegoat_tree::valid_alpha(a, b) {
+                Ok(Kind::Scapegoat(a, b))
+            } else {
+                Err(format!("alpha = {a} / {b} must lie strictly between 1/2 and 1"))
+            }
+        },
+        _ => Err(format!("unknown backend {name}, expected bst, avl or sg [a b]")),
+    }
+}
+
+fn parse_annotation(word: &str) -> Result<Annotation, String> {
+    match word {
+        "balance" => Ok(Annotation::Balance),
+        "size" => Ok(Annotation::Size),
+        "height" => Ok(Annotation::Height),
+        _ => Err(format!("unknown annotation {word}, expected balance, size or height")),
+    }
+}
+
+fn parse_key(word: &str) -> Result<i64, String> {
+    word.parse().map_err(|_| format!("invalid key {word}"))
+}
+
+// Parses every key before any is used so a typo leaves the tree untouched
+fn parse_keys(words: &[&str]) -> Result<Vec<i64>, String> {
+    words.iter().map(|word| parse_key(word)).collect()
+}
+
+// Appends text to out on a line of its own, skipping empty text
+fn push_line(out: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(text);
+}
+
+fn print_text(output: &mut impl Write, text: &str) -> io::Result<()> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    writeln!(output, "{text}")
+}
@@ -1,86 +1,1162 @@
-use std::{mem, ptr, cmp::Ordering};
+//! Scapegoat tree: an α-weight-balanced binary search tree rebalanced by
+//! rebuilding subtrees rather than by rotations.
 
-pub struct SGTree<T> {
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt::{self, Display},
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::bulk;
+use crate::cursor::{self, Branch, Zipper};
+use crate::export::{self, Diagram};
+use crate::pretty::{self, Style};
+use crate::stats;
+pub use crate::traversal::Order;
+use crate::traversal::{self, Shape, Span, Split, Traversal};
+use crate::validate::{self, Problem, Scapegoat, Violation};
+use crate::OrderedMap;
+
+/// Scapegoat tree: a binary search tree kept α-weight-balanced, with α = a / b,
+/// by rebuilding whole subtrees. Nodes carry no balance metadata, only the
+/// size of their subtree; the tree counts its nodes (n) and the largest n
+/// since the last full rebuild (m).
+pub struct SGTree<K, V> {
     a: u32,
     b: u32,
-    m: u32,
-    n: u32,
-    root: Option<Node<T>>,
+    m: usize,
+    n: usize,
+    root: Option<Box<Node<K, V>>>,
 }
 
-pub struct Node<T> {
-    key: u32,
-    value: T,
-    left_sub: Option<Box<SGTree<T>>>,
-    right_sub: Option<Box<SGTree<T>>>,
-    parent: Option<*mut Node<T>>, 
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Number of nodes in the subtree rooted here, this one included
+    size: usize,
+    left_sub: Option<Box<Node<K, V>>>,
+    right_sub: Option<Box<Node<K, V>>>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Node<K, V> {
+        Node { key, value, size: 1, left_sub: None, right_sub: None }
+    }
+
+    // Returns the child subtree on side of the node (Less is left)
+    fn child(&self, side: Ordering) -> &Option<Box<Node<K, V>>> {
+        match side {
+            Ordering::Less => &self.left_sub,
+            _ => &self.right_sub,
+        }
+    }
+
+    // Returns the child subtree on side of the node (Less is left)
+    fn child_mut(&mut self, side: Ordering) -> &mut Option<Box<Node<K, V>>> {
+        match side {
+            Ordering::Less => &mut self.left_sub,
+            _ => &mut self.right_sub,
+        }
+    }
 }
 
-impl<T> Node<T> {
-    pub fn new(key: u32, value: T, parent: Option<* mut Node<T>>) -> Node<T> {
-        Node {key, value, left_sub: None, right_sub: None, parent}
+// Defaults to α = 2/3
+impl<K: Ord, V> Default for SGTree<K, V> {
+    fn default() -> SGTree<K, V> {
+        SGTree::new(2, 3, None)
     }
 }
 
-impl<T> SGTree<T> {
-    pub fn new(a: u32, b: u32, root: Option<(u32, T)>) -> SGTree<T> {
-         SGTree { a, b, m: 0, n: 0, root: None }
+/// Returns true if α = a / b lies strictly between 1/2 and 1, as every
+/// scapegoat tree's must
+pub fn valid_alpha(a: u32, b: u32) -> bool {
+    b < 2 * a && a < b
+}
+
+impl<K: Ord, V> SGTree<K, V> {
+    /// Returns a new tree with α = a / b, initiated with no root or Node of key and value
+    /// Panics unless 1/2 < α < 1
+    pub fn new(a: u32, b: u32, root: Option<(K, V)>) -> SGTree<K, V> {
+        assert!(valid_alpha(a, b), "alpha = a / b must lie strictly between 1/2 and 1");
+        let mut tree = SGTree { a, b, m: 0, n: 0, root: None };
+        if let Some((key, value)) = root {
+            tree.insert(key, value);
+        }
+        tree
+    }
+
+    /// Returns a perfectly balanced tree with α = a / b of the (key, value)
+    /// pairs of iter, built in linear time
+    /// Panics unless 1/2 < α < 1 and keys are strictly ascending
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(a: u32, b: u32, iter: I) -> SGTree<K, V> {
+        let mut tree = SGTree::new(a, b, None);
+        tree.fill(bulk::sorted(iter));
+        tree
+    }
+
+    // Make an empty tree a perfectly balanced one of items, whose keys are
+    // strictly ascending
+    fn fill(&mut self, items: Vec<(K, V)>) {
+        let n = items.len();
+        self.root = bulk::build(&mut items.into_iter(), n, &mut |(key, value), left_sub, right_sub| {
+            Box::new(Node { key, value, size: 1 + size(&left_sub) + size(&right_sub), left_sub, right_sub })
+        });
+        // As after a full rebuild, the tree is as large as it has been since
+        self.n = n;
+        self.m = n;
+    }
+
+    /// Insert node with key into tree
+    /// Rebuilds the subtree rooted at a scapegoat if the new node lands too deep
+    ///
+    /// Returns: None if key was not present, else Some with the replaced value
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // Sides taken from the root down to the new node
+        let mut path = Vec::new();
+        let mut link = &mut self.root;
+        while let Some(node) = link {
+            let side = key.cmp(&node.key);
+            if side == Ordering::Equal {
+                return Some(mem::replace(&mut node.value, value));
+            }
+            link = node.child_mut(side);
+            path.push(side);
+        }
+        *link = Some(Box::new(Node::new(key, value)));
+        self.grow(&path, None);
+        self.debug_validate();
+        None
+    }
+
+    /// Delete node with key in tree
+    /// Rebuilds the whole tree once n drops below α·m
+    ///
+    /// Returns: Some with the removed key and value, or None if key was not found
+    pub fn delete<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        // The key is present, so every node on the way down loses a descendant
+        let mut link = &mut self.root;
+        loop {
+            let side = key.cmp(link.as_ref().unwrap().key.borrow());
+            if side == Ordering::Equal {
+                break;
+            }
+            let node = link.as_mut().unwrap();
+            node.size -= 1;
+            link = node.child_mut(side);
+        }
+        let removed = Self::unlink(link);
+        self.shrink(None);
+        self.debug_validate();
+        Some(removed)
+    }
+
+    /// Find and extract the minimum key of a tree
+    /// Rebuilds the whole tree once n drops below α·m, as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_min(&mut self) -> Option<(K, V)> {
+        self.extract(Ordering::Less)
+    }
+
+    /// Find and extract the maximum key of a tree
+    /// Rebuilds the whole tree once n drops below α·m, as delete does
+    ///
+    /// Returns: Some<(K, V)> or None on empty tree
+    pub fn extract_max(&mut self) -> Option<(K, V)> {
+        self.extract(Ordering::Greater)
+    }
+
+    // Unlink the node at the end of the spine on side (Less is left)
+    fn extract(&mut self, side: Ordering) -> Option<(K, V)> {
+        let mut link = &mut self.root;
+        while link.as_ref()?.child(side).is_some() {
+            let node = link.as_mut().unwrap();
+            node.size -= 1;
+            link = node.child_mut(side);
+        }
+        let removed = Self::unlink(link);
+        self.shrink(None);
+        self.debug_validate();
+        Some(removed)
+    }
+
+    /// Returns a reference to the value stored under key, if any
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => node.left_sub.as_deref(),
+                Ordering::Greater => node.right_sub.as_deref(),
+            };
+        }
+        None
+    }
+
+    /// Returns a mutable reference to the value stored under key, if any
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = self.root.as_deref_mut();
+        while let Some(node) = current {
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left_sub.as_deref_mut(),
+                Ordering::Greater => node.right_sub.as_deref_mut(),
+            };
+        }
+        None
     }
 
-    pub fn insert(&mut self, key: u32, value: T) -> bool {
-        match self.root.is_none() {
-            true => {
-                self.root = Some(Node::new(key, value, None));
-                self.m += 1;
-                self.n += 1;
-                true
+    /// Returns true if a node with key exists in the tree
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Check the search tree order of the keys, the subtree sizes nodes store,
+    /// that no node lies deeper than floor(log_{1/α} m), and that
+    /// α·m <= n <= m with n the number of nodes
+    ///
+    /// Returns: Err with every violation found, each with the path to the offending node
+    pub fn validate(&self) -> Result<(), Vec<Violation<'_, K>>> {
+        let limits = Scapegoat { a: self.a, b: self.b, depth: self.max_depth(self.m) };
+        let mut violations = validate::check(self.root.as_deref(), Some(limits));
+        let actual = count(&self.root);
+        let whole = |problem| Violation { path: Vec::new(), key: None, problem };
+        if actual != self.n {
+            violations.push(whole(Problem::Count { stored: self.n, actual }));
+        }
+        if self.n > self.m || self.n * (self.b as usize) < self.m * (self.a as usize) {
+            violations.push(whole(Problem::Bounds { n: self.n, m: self.m }));
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    // Check every invariant after a change, when built with the validate feature
+    fn debug_validate(&self) {
+        if cfg!(feature = "validate") {
+            assert!(self.validate().is_ok(), "invariants broken: {:?}", validate::problems(self.validate()));
+        }
+    }
+
+    /// Find key in self
+    ///
+    /// Returns Vec of nodes visited to find key
+    /// Returns empty Vec if key was not found
+    pub fn search<Q>(&self, key: &Q) -> Vec<&K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut vec = Vec::new();
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            vec.push(&node.key);
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return vec,
+                Ordering::Less => node.left_sub.as_deref(),
+                Ordering::Greater => node.right_sub.as_deref(),
+            };
+        }
+        Vec::new()
+    }
+
+    /// Iterate in ascending key order over the (key, value) pairs whose keys lie in range
+    /// Only subtrees overlapping the range are visited, from either end
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range(Span::new(
+            self.root.as_deref(),
+            |key| traversal::below(key.borrow(), range.start_bound()),
+            |key| traversal::above(key.borrow(), range.end_bound()),
+        ))
+    }
+
+    /// Returns the (key, value) pair with the greatest key less than or equal to key
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Included(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key greater than or equal to key
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Included(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the (key, value) pair with the greatest key strictly less than key
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Unbounded, Bound::Excluded(key))).next_back()
+    }
+
+    /// Returns the (key, value) pair with the least key strictly greater than key
+    pub fn successor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.range((Bound::Excluded(key), Bound::Unbounded)).next()
+    }
+
+    /// Returns the number of keys in the tree less than key, whether or not key is present
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            current = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return rank + size(&node.left_sub),
+                Ordering::Less => node.left_sub.as_deref(),
+                Ordering::Greater => {
+                    rank += size(&node.left_sub) + 1;
+                    node.right_sub.as_deref()
+                },
+            };
+        }
+        rank
+    }
+
+    /// Returns a cursor on the least key, or on the ghost position if the tree is empty
+    pub fn cursor_front(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Less);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the greatest key, or on the ghost position if the tree is empty
+    pub fn cursor_back(&self) -> Cursor<'_, K, V> {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.end(Ordering::Greater);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor on the least key greater than or equal to key, or on
+    /// the ghost position if there is none
+    pub fn cursor<Q>(&self, key: &Q) -> Cursor<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut zipper = Zipper::new(self.root.as_deref());
+        zipper.lower_bound(|node| node.key.borrow() < key);
+        Cursor(zipper)
+    }
+
+    /// Returns a cursor that edits the tree, on the least key
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Less);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the greatest key
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.end(Ordering::Greater);
+        cursor
+    }
+
+    /// Returns a cursor that edits the tree, on the least key greater than or
+    /// equal to key, or on the ghost position if there is none
+    pub fn cursor_mut<Q>(&mut self, key: &Q) -> CursorMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut cursor = CursorMut::new(self);
+        cursor.zipper.lower_bound(|node| node.key.borrow() < key);
+        cursor
+    }
+}
+
+impl<K, V> SGTree<K, V> {
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// Returns true if the tree holds no nodes
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Returns the number of levels in the tree, 0 when empty
+    pub fn height(&self) -> usize {
+        traversal::height(self.root.as_deref())
+    }
+
+    /// Returns the bytes of heap memory each entry takes: one boxed node with
+    /// its key, value and links, not counting allocator overhead
+    pub fn node_bytes() -> usize {
+        mem::size_of::<Node<K, V>>()
+    }
+
+    /// Draw the shape of the tree as text in the given style
+    pub fn render(&self, style: &Style) -> String
+    where
+        K: Display,
+    {
+        pretty::render(self.root.as_deref(), style)
+    }
+
+    /// Write the tree as a Graphviz DOT document showing what diagram asks for
+    pub fn to_dot(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::dot(self.root.as_deref(), diagram)
+    }
+
+    /// Write the tree as a standalone SVG image showing what diagram asks for
+    pub fn to_svg(&self, diagram: &Diagram<'_, K>) -> String
+    where
+        K: Display + PartialEq,
+    {
+        export::svg(self.root.as_deref(), diagram)
+    }
+
+    /// Returns the balance parameter α as the fraction (a, b)
+    pub fn alpha(&self) -> (u32, u32) {
+        (self.a, self.b)
+    }
+
+    /// Returns the (key, value) pair at zero based position i in ascending key order
+    pub fn select(&self, mut i: usize) -> Option<(&K, &V)> {
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            let left = size(&node.left_sub);
+            current = match i.cmp(&left) {
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Less => node.left_sub.as_deref(),
+                Ordering::Greater => {
+                    i -= left + 1;
+                    node.right_sub.as_deref()
+                },
+            };
+        }
+        None
+    }
+
+    /// Returns the lower median (key, value) pair, or None on empty tree
+    pub fn median(&self) -> Option<(&K, &V)> {
+        self.select(self.len().saturating_sub(1) / 2)
+    }
+
+    /// Iterate over (key, value) pairs in ascending key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.iter_in(Order::In)
+    }
+
+    /// Iterate over (key, value) pairs in the given traversal order
+    pub fn iter_in(&self, order: Order) -> Iter<'_, K, V> {
+        Iter(Traversal::new(self.root.as_deref(), order))
+    }
+
+    /// Iterate over (key, mutable value) pairs in ascending key order
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.iter_mut_in(Order::In)
+    }
+
+    /// Iterate over (key, mutable value) pairs in the given traversal order
+    pub fn iter_mut_in(&mut self, order: Order) -> IterMut<'_, K, V> {
+        IterMut(Traversal::new(self.root.as_deref_mut(), order))
+    }
+
+    /// Consume the tree, yielding owned (key, value) pairs in the given traversal order
+    pub fn into_iter_in(self, order: Order) -> IntoIter<K, V> {
+        IntoIter(Traversal::new(self.root, order))
+    }
+
+    /// Iterate over keys in ascending order
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    /// Iterate over keys in the given traversal order
+    pub fn keys_in(&self, order: Order) -> Keys<'_, K, V> {
+        Keys(self.iter_in(order))
+    }
+
+    /// Iterate over values in ascending key order
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    /// Iterate over values in the given traversal order
+    pub fn values_in(&self, order: Order) -> Values<'_, K, V> {
+        Values(self.iter_in(order))
+    }
+
+    // Deepest a node may sit in an α-height-balanced tree of size nodes:
+    // floor(log_{1/α} size)
+    fn max_depth(&self, size: usize) -> usize {
+        ((size as f64).ln() / (self.b as f64 / self.a as f64).ln()).floor() as usize
+    }
+
+    // Walk back up the path to a node that was inserted too deep until
+    // reaching an ancestor whose child on the path holds more than α of its nodes
+    // Returns: the depth of the scapegoat, which always exists above a too deep node
+    fn scapegoat(&self, path: &[Ordering]) -> usize {
+        let mut ancestors = Vec::with_capacity(path.len());
+        let mut current = self.root.as_deref();
+        for &side in path {
+            let node = current.unwrap();
+            ancestors.push(node);
+            current = node.child(side).as_deref();
+        }
+        for (depth, node) in ancestors.into_iter().enumerate().rev() {
+            let child_size = size(node.child(path[depth]));
+            if (self.b as usize) * child_size > (self.a as usize) * node.size {
+                return depth;
+            }
+        }
+        unreachable!("a too deep node has a scapegoat ancestor")
+    }
+
+    // Count a node just linked in at the end of path in n and in the sizes of
+    // its ancestors, rebuilding the subtree rooted at a scapegoat if the node
+    // lies too deep
+    // mark is passed on to rebuild
+    fn grow(&mut self, path: &[Ordering], mark: Option<&mut Vec<Ordering>>) {
+        let mut link = &mut self.root;
+        for &side in path {
+            let node = link.as_mut().unwrap();
+            node.size += 1;
+            link = node.child_mut(side);
+        }
+        self.n += 1;
+        self.m = self.m.max(self.n);
+        if path.len() > self.max_depth(self.n) {
+            let depth = self.scapegoat(path);
+            self.rebuild(&path[..depth], mark);
+        }
+    }
+
+    // Count a removed node, rebuilding the whole tree once n drops below α·m
+    // mark is passed on to rebuild
+    fn shrink(&mut self, mark: Option<&mut Vec<Ordering>>) {
+        self.n -= 1;
+        if self.n * (self.b as usize) < self.m * (self.a as usize) {
+            self.rebuild(&[], mark);
+            self.m = self.n;
+        }
+    }
+
+    // Rebuild the subtree reached by following path from the root into a
+    // perfectly balanced one in linear time. If mark, the path to some node,
+    // leads into the subtree, it is changed to where that node ends up.
+    fn rebuild(&mut self, path: &[Ordering], mark: Option<&mut Vec<Ordering>>) {
+        let mark = mark.filter(|mark| mark.starts_with(path));
+        let offset = mark.as_ref().map(|mark| self.offset(mark, path.len()));
+        let mut link = &mut self.root;
+        for &side in path {
+            link = link.as_mut().unwrap().child_mut(side);
+        }
+        let nodes = flatten(link.take());
+        let len = nodes.len();
+        stats::rebuild(len);
+        *link = bulk::build(&mut nodes.into_iter(), len, &mut |mut node, left_sub, right_sub| {
+            node.size = 1 + size(&left_sub) + size(&right_sub);
+            node.left_sub = left_sub;
+            node.right_sub = right_sub;
+            node
+        });
+        if cfg!(feature = "validate") {
+            assert_eq!(traversal::height(link.as_deref()), bulk::height(len), "rebuilt subtree is not balanced");
+        }
+        if let (Some(mark), Some(offset)) = (mark, offset) {
+            mark.truncate(path.len());
+            mark.extend(built_path(len, offset));
+        }
+    }
+
+    // Returns the node at the end of path
+    fn node_at(&self, path: &[Ordering]) -> &Node<K, V> {
+        let mut node = self.root.as_deref().unwrap();
+        for &side in path {
+            node = node.child(side).as_deref().unwrap();
+        }
+        node
+    }
+
+    // Returns the number of nodes before the one at the end of path in
+    // ascending key order, counting only the subtree at depth on the path
+    fn offset(&self, path: &[Ordering], depth: usize) -> usize {
+        let mut offset = 0;
+        let mut node = self.root.as_deref().unwrap();
+        for (i, &side) in path.iter().enumerate() {
+            if i >= depth && side == Ordering::Greater {
+                offset += size(&node.left_sub) + 1;
+            }
+            node = node.child(side).as_deref().unwrap();
+        }
+        offset + size(&node.left_sub)
+    }
+
+    // Insert a node next to the one at the end of mark on side (Greater is
+    // after it), or at the end of the tree opposite side if there is no mark,
+    // rebuilding as insert does
+    fn insert_next_to(&mut self, mark: &mut Option<Vec<Ordering>>, side: Ordering, key: K, value: V) {
+        let mut path = mark.clone().unwrap_or_default();
+        let mut link = &mut self.root;
+        for &step in &path {
+            link = link.as_mut().unwrap().child_mut(step);
+        }
+        if mark.is_some() {
+            link = link.as_mut().unwrap().child_mut(side);
+            path.push(side);
+        }
+        while let Some(node) = link {
+            link = node.child_mut(side.reverse());
+            path.push(side.reverse());
+        }
+        *link = Some(Box::new(Node::new(key, value)));
+        self.grow(&path, mark.as_mut());
+    }
+
+    // Remove the node at the end of mark, rebuilding as delete does, and move
+    // mark to the node after it, or make it None if there is none
+    // Returns: the removed key and value
+    fn remove_marked(&mut self, mark: &mut Option<Vec<Ordering>>) -> (K, V) {
+        let path = mark.as_mut().unwrap();
+        let mut link = &mut self.root;
+        for &side in path.iter() {
+            let node = link.as_mut().unwrap();
+            node.size -= 1;
+            link = node.child_mut(side);
+        }
+        let node = link.as_ref().unwrap();
+        let (left, right) = (node.left_sub.is_some(), node.right_sub.is_some());
+        let removed = Self::unlink(link);
+        // With both children, the next key and value moved into the node
+        if right && !left {
+            // The right child took the node's place, and its least key comes next
+            let mut node = self.node_at(path);
+            while let Some(child) = node.left_sub.as_deref() {
+                node = child;
+                path.push(Ordering::Less);
+            }
+        } else if !right {
+            // The nearest ancestor whose left subtree held the node comes next
+            match path.iter().rposition(|side| *side == Ordering::Less) {
+                Some(depth) => path.truncate(depth),
+                None => *mark = None,
+            }
+        }
+        self.shrink(mark.as_mut());
+        removed
+    }
+
+    // Remove the node held by link, replacing it with a child or its successor.
+    // The sizes of the nodes above link are left for the caller to update.
+    // Returns: the removed key and value
+    fn unlink(link: &mut Option<Box<Node<K, V>>>) -> (K, V) {
+        let node = link.as_mut().unwrap();
+        if node.left_sub.is_some() && node.right_sub.is_some() {
+            // Current has both descendents, successor key and value take its place
+            node.size -= 1;
+            let mut successor = &mut node.right_sub;
+            while successor.as_ref().unwrap().left_sub.is_some() {
+                let next = successor.as_mut().unwrap();
+                next.size -= 1;
+                successor = &mut next.left_sub;
+            }
+            let (key, value) = Self::unlink(successor);
+            return (mem::replace(&mut node.key, key), mem::replace(&mut node.value, value));
+        }
+        // Current is a leaf or has a single child which replaces it
+        let mut node = link.take().unwrap();
+        *link = node.left_sub.take().or(node.right_sub.take());
+        (node.key, node.value)
+    }
+}
+
+// Count the nodes of a subtree by visiting them all
+fn count<K, V>(root: &Option<Box<Node<K, V>>>) -> usize {
+    Traversal::new(root.as_deref(), Order::Pre).count()
+}
+
+// Returns the size a subtree's root stores, 0 for an empty one
+fn size<K, V>(root: &Option<Box<Node<K, V>>>) -> usize {
+    root.as_ref().map_or(0, |node| node.size)
+}
+
+// Detach every node of a subtree, returning them in ascending key order
+fn flatten<K, V>(root: Option<Box<Node<K, V>>>) -> Vec<Box<Node<K, V>>> {
+    let mut nodes = Vec::new();
+    let mut stack = Vec::new();
+    let mut current = root;
+    loop {
+        while let Some(mut node) = current {
+            current = node.left_sub.take();
+            stack.push(node);
+        }
+        match stack.pop() {
+            None => return nodes,
+            Some(mut node) => {
+                current = node.right_sub.take();
+                nodes.push(node);
+            },
+        }
+    }
+}
+
+// Returns the sides leading from the root of a subtree that bulk::build made
+// of len nodes down to the node at zero based position offset in it
+fn built_path(mut len: usize, mut offset: usize) -> Vec<Ordering> {
+    let mut sides = Vec::new();
+    loop {
+        let mid = (len - 1) / 2;
+        let side = offset.cmp(&mid);
+        match side {
+            Ordering::Equal => return sides,
+            Ordering::Less => len = mid,
+            Ordering::Greater => {
+                offset -= mid + 1;
+                len -= mid + 1;
             },
-            false => {
-                match key.cmp(&self.root.as_ref().unwrap().key){
-                    Ordering::Equal => false,
-                    Ordering::Less => match self.root.as_ref().unwrap().left_sub.is_none() {
-                        true => {
-                            self.root.as_mut().unwrap().left_sub = Some(
-                                Box::new(SGTree { 
-                                    a: self.a, b: self.b, m: 1, n: 1, root: Some(
-                                    Node::new(
-                                        key, value, 
-                                        Some(self.root.as_mut().unwrap())
-                                    )
-                                )
-                            }
-                            ));
-                            self.m += 1;
-                            self.n += 1;
-                            true
-                        },
-                        false => {
-                            self.root.as_mut().unwrap().left_sub.as_mut().unwrap().insert(key, value)
-                        }
-                    } 
-                    Ordering::Greater => match self.root.as_ref().unwrap().right_sub.is_none() {
-                        true => {
-                            self.root.as_mut().unwrap().right_sub = Some(
-                                Box::new(SGTree { 
-                                    a: self.a, b: self.b, m: 1, n: 1, root: Some(
-                                    Node::new(
-                                        key, value, 
-                                        Some(self.root.as_mut().unwrap())
-                                    )
-                                )
-                            }
-                            ));
-                            self.m += 1;
-                            self.n += 1;
-                            true
-                        },
-                        false => {
-                            self.root.as_mut().unwrap().right_sub.as_mut().unwrap().insert(key, value)
-                        }
-                    }
+        }
+        sides.push(side);
+    }
+}
+
+impl<'a, K, V> Split for &'a Node<K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        ((&self.key, &self.value), self.left_sub.as_deref(), self.right_sub.as_deref())
+    }
+}
+
+impl<K, V> Branch for Node<K, V> {
+    fn subtree(&self, side: Ordering) -> Option<&Self> {
+        self.child(side).as_deref()
+    }
+
+    fn link(&mut self, side: Ordering) -> &mut Option<Box<Self>> {
+        self.child_mut(side)
+    }
+}
+
+/// Draws the tree in the default [`Style`]. A width, as in `{:40}`, wraps
+/// the drawing to that many columns.
+impl<K: Display, V> fmt::Display for SGTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = Style { width: f.width(), ..Style::default() };
+        f.write_str(&self.render(&style))
+    }
+}
+
+impl<K, V> Shape for &Node<K, V> {
+    fn size(self) -> Option<usize> {
+        Some(self.size)
+    }
+}
+
+impl<'a, K, V> Split for &'a mut Node<K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let Node { key, value, left_sub, right_sub, .. } = self;
+        ((&*key, value), left_sub.as_deref_mut(), right_sub.as_deref_mut())
+    }
+}
+
+impl<K, V> Split for Box<Node<K, V>> {
+    type Item = (K, V);
+
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>) {
+        let node = *self;
+        ((node.key, node.value), node.left_sub, node.right_sub)
+    }
+}
+
+/// Iterator over borrowed (key, value) pairs of an SGTree
+pub struct Iter<'a, K, V>(Traversal<&'a Node<K, V>>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Iterator over (key, mutable value) pairs of an SGTree
+pub struct IterMut<'a, K, V>(Traversal<&'a mut Node<K, V>>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Owning iterator over (key, value) pairs of an SGTree
+pub struct IntoIter<K, V>(Traversal<Box<Node<K, V>>>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Double ended iterator over the (key, value) pairs of an SGTree within a range
+pub struct Range<'a, K, V>(Span<&'a Node<K, V>>);
+
+impl<'a, K: Ord, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+/// A cursor over the (key, value) pairs of an SGTree in ascending key order.
+/// Besides the pairs, it may sit on a ghost position past both ends: moving
+/// past either end reaches the ghost, and moving on from the ghost wraps
+/// around to the other end. Each move takes amortized O(1).
+pub struct Cursor<'a, K, V>(Zipper<&'a Node<K, V>>);
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&'a K> {
+        self.0.handle().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&'a V> {
+        self.0.handle().map(|node| &node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.0.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.0.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.0.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+}
+
+/// A cursor that moves as [`Cursor`] does, and also modifies values and
+/// inserts and removes keys next to its position, rebuilding subtrees as
+/// insert and delete do. The tree is whole again once the cursor is dropped.
+pub struct CursorMut<'a, K, V> {
+    tree: &'a mut SGTree<K, V>,
+    // Holds the nodes of tree, which is left empty while the cursor lives
+    zipper: Zipper<Box<Node<K, V>>>,
+}
+
+impl<'a, K, V> CursorMut<'a, K, V> {
+    // Returns a cursor on the ghost position of tree
+    fn new(tree: &'a mut SGTree<K, V>) -> CursorMut<'a, K, V> {
+        CursorMut { zipper: Zipper::new(tree.root.take()), tree }
+    }
+
+    /// Returns the key the cursor is on, None on the ghost
+    pub fn key(&self) -> Option<&K> {
+        self.zipper.current().map(|node| &node.key)
+    }
+
+    /// Returns the value the cursor is on, None on the ghost
+    pub fn value(&self) -> Option<&V> {
+        self.zipper.current().map(|node| &node.value)
+    }
+
+    /// Returns a mutable reference to the value the cursor is on, None on the ghost
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.zipper.current_mut().map(|node| &mut node.value)
+    }
+
+    /// Move to the next key in ascending order
+    pub fn move_next(&mut self) {
+        self.zipper.step(Ordering::Greater);
+    }
+
+    /// Move to the previous key in ascending order
+    pub fn move_prev(&mut self) {
+        self.zipper.step(Ordering::Less);
+    }
+
+    /// Returns the (key, value) pair move_next would move to, None for the ghost
+    pub fn peek_next(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Greater).map(|node| (&node.key, &node.value))
+    }
+
+    /// Returns the (key, value) pair move_prev would move to, None for the ghost
+    pub fn peek_prev(&self) -> Option<(&K, &V)> {
+        self.zipper.neighbour(Ordering::Less).map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K: Ord, V> CursorMut<'_, K, V> {
+    /// Insert key and value just after the cursor, or at the front from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the key the cursor is on and the next
+    pub fn insert_after(&mut self, key: K, value: V) {
+        let next = self.peek_next().map(|(next, _)| next);
+        assert!(cursor::between(self.key(), &key, next), "key must lie strictly between the keys around the cursor");
+        self.edit(|tree, mark| tree.insert_next_to(mark, Ordering::Greater, key, value));
+    }
+
+    /// Insert key and value just before the cursor, or at the back from the
+    /// ghost, without moving the cursor
+    /// Panics unless key lies strictly between the previous key and the key the cursor is on
+    pub fn insert_before(&mut self, key: K, value: V) {
+        let prev = self.peek_prev().map(|(prev, _)| prev);
+        assert!(cursor::between(prev, &key, self.key()), "key must lie strictly between the keys around the cursor");
+        self.edit(|tree, mark| tree.insert_next_to(mark, Ordering::Less, key, value));
+    }
+
+    /// Remove the pair the cursor is on and move to the next key
+    ///
+    /// Returns: the removed key and value, or None on the ghost
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        if self.zipper.is_ghost() {
+            return None;
+        }
+        Some(self.edit(|tree, mark| tree.remove_marked(mark)))
+    }
+
+    // Apply edit to the whole tree and the path to the cursor's node, None on
+    // the ghost, then put the cursor at the end of the path edit leaves.
+    // The path is followed through any rebuild, which moves the node.
+    fn edit<T>(&mut self, edit: impl FnOnce(&mut SGTree<K, V>, &mut Option<Vec<Ordering>>) -> T) -> T {
+        let mut mark = (!self.zipper.is_ghost()).then(|| self.zipper.sides());
+        self.tree.root = self.zipper.take();
+        let result = edit(self.tree, &mut mark);
+        self.tree.debug_validate();
+        self.zipper = Zipper::new(self.tree.root.take());
+        if let Some(mark) = mark {
+            let mut sides = mark.into_iter();
+            self.zipper.seek(|_| sides.next().unwrap_or(Ordering::Equal));
+        }
+        result
+    }
+}
+
+impl<K, V> Drop for CursorMut<'_, K, V> {
+    fn drop(&mut self) {
+        self.tree.root = self.zipper.take();
+    }
+}
+
+/// Iterator over borrowed keys of an SGTree
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+/// Iterator over borrowed values of an SGTree
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> IntoIterator for SGTree<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        self.into_iter_in(Order::In)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SGTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut SGTree<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+// Extending an empty tree with sorted input builds it in linear time; any
+// other input is inserted pair by pair, replacing the values of keys present
+impl<K: Ord, V> Extend<(K, V)> for SGTree<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let items: Vec<(K, V)> = iter.into_iter().collect();
+        if self.is_empty() && bulk::ascending(&items) {
+            self.fill(items);
+            return;
+        }
+        for (key, value) in items {
+            OrderedMap::insert(self, key, value);
+        }
+    }
+}
+
+// Collects into a tree with α = 2/3
+impl<K: Ord, V> FromIterator<(K, V)> for SGTree<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> SGTree<K, V> {
+        let mut tree = SGTree::default();
+        tree.extend(iter);
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    // Check that the tree holds model, that α·m <= n <= m, and that no node
+    // lies deeper than floor(log_{1/α} m)
+    fn check(tree: &SGTree<u32, u32>, model: &BTreeMap<u32, u32>) {
+        assert_eq!(tree.len(), model.len());
+        assert!(tree.n <= tree.m && tree.m * (tree.a as usize) <= tree.n * (tree.b as usize));
+        let limit = ((tree.m as f64).ln() / (tree.b as f64 / tree.a as f64).ln()).floor() as usize;
+        let mut stack: Vec<_> = tree.root.as_deref().map(|node| (node, 0)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            assert!(depth <= limit, "node at depth {depth} of a tree limited to {limit}");
+            stack.extend(node.left_sub.as_deref().map(|child| (child, depth + 1)));
+            stack.extend(node.right_sub.as_deref().map(|child| (child, depth + 1)));
+        }
+        for (key, value) in model {
+            assert_eq!(tree.get(key), Some(value));
+        }
+    }
+
+    // Pseudo-random keys below bound from a linear congruential generator
+    fn keys(seed: u64, bound: u64) -> impl Iterator<Item = u32> {
+        let mut state = seed;
+        std::iter::repeat_with(move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % bound) as u32
+        })
+    }
+
+    #[test]
+    fn sorted_inserts_find_scapegoats_on_the_way_up() {
+        for (a, b) in [(2, 3), (3, 4), (5, 9)] {
+            let mut tree = SGTree::new(a, b, None);
+            let mut model = BTreeMap::new();
+            for key in (0..300).chain((300..600).rev()) {
+                assert_eq!(tree.insert(key, key), None);
+                model.insert(key, key);
+                check(&tree, &model);
+            }
+            assert_eq!(tree.insert(7, 0), Some(7));
+            assert_eq!(tree.get(&7), Some(&0));
+        }
+    }
+
+    #[test]
+    fn inserts_and_deletes_match_btreemap() {
+        for seed in 0..4 {
+            let mut tree = SGTree::new(2, 3, None);
+            let mut model = BTreeMap::new();
+            for (step, key) in keys(seed, 200).take(600).enumerate() {
+                if step % 3 == 0 {
+                    assert_eq!(tree.delete(&key), model.remove_entry(&key));
+                } else {
+                    assert_eq!(tree.insert(key, step as u32), model.insert(key, step as u32));
                 }
+                check(&tree, &model);
             }
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn trees_keep_working_after_moving() {
+        // Each move changes the address of the tree and its root link
+        let mut trees: Vec<SGTree<u32, u32>> = Vec::new();
+        let mut model = BTreeMap::new();
+        let mut tree = SGTree::new(3, 4, Some((0, 0)));
+        model.insert(0, 0);
+        for (step, key) in keys(9, 400).take(400).enumerate() {
+            tree.insert(key, step as u32);
+            model.insert(key, step as u32);
+            if step % 5 == 0 {
+                assert_eq!(tree.delete(&key), model.remove_entry(&key));
+            }
+            // Park the tree among others, which reallocate as they grow
+            trees.push(SGTree::new(2, 3, None));
+            trees.push(tree);
+            let boxed = Box::new(trees.swap_remove(trees.len() - 1));
+            tree = *boxed;
+            check(&tree, &model);
+        }
+    }
+}
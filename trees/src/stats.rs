@@ -0,0 +1,79 @@
+//! Counters of the rebalancing work trees do, for benchmarks and tuning.
+//!
+//! AVL trees count every single rotation; scapegoat trees count every subtree
+//! rebuild and the nodes it relinks. Counts are kept per thread and shared by
+//! all trees on it, so measure one tree at a time:
+//!
+//! ```
+//! use trees::{stats, AvlTree};
+//!
+//! stats::reset();
+//! let mut tree = AvlTree::default();
+//! for key in 0..7 {
+//!     tree.insert(key, ());
+//! }
+//! if stats::ENABLED {
+//!     assert_eq!(stats::counters().rotations, 4);
+//! }
+//! ```
+//!
+//! Counting costs a little on every rotation and rebuild, so it only happens
+//! when the crate is built with the `stats` feature. Without it every count
+//! stays zero.
+use std::cell::Cell;
+
+/// True if the crate counts its rebalancing work, having been built with the
+/// `stats` feature
+pub const ENABLED: bool = cfg!(feature = "stats");
+
+/// Rebalancing work done on the current thread since the last reset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Single rotations by AVL trees; a double rotation counts as two
+    pub rotations: u64,
+    /// Subtree rebuilds by scapegoat trees, including full rebuilds after deletes
+    pub rebuilds: u64,
+    /// Nodes relinked by those rebuilds
+    pub rebuilt_nodes: u64,
+}
+
+thread_local! {
+    static COUNTERS: Cell<Counters> = const {
+        Cell::new(Counters { rotations: 0, rebuilds: 0, rebuilt_nodes: 0 })
+    };
+}
+
+/// Returns the counts for the current thread
+pub fn counters() -> Counters {
+    COUNTERS.with(Cell::get)
+}
+
+/// Set all counts for the current thread back to zero
+pub fn reset() {
+    COUNTERS.with(|counters| counters.set(Counters::default()));
+}
+
+// Count one rotation
+#[inline]
+pub(crate) fn rotation() {
+    if ENABLED {
+        COUNTERS.with(|counters| {
+            let mut counts = counters.get();
+            counts.rotations += 1;
+            counters.set(counts);
+        });
+    }
+}
+
+// Count a rebuild of a subtree of nodes nodes
+#[inline]
+pub(crate) fn rebuild(nodes: usize) {
+    if ENABLED {
+        COUNTERS.with(|counters| {
+            let mut counts = counters.get();
+            counts.rebuilds += 1;
+            counts.rebuilt_nodes += nodes as u64;
+            counters.set(counts);
+        });
+    }
+}
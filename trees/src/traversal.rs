@@ -0,0 +1,267 @@
+use std::{collections::VecDeque, ops::Bound};
+
+/// Order in which a traversal visits the nodes of a tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Node, then left subtree, then right subtree
+    Pre,
+    /// Left subtree, then node, then right subtree (ascending keys)
+    In,
+    /// Left subtree, then right subtree, then node
+    Post,
+    /// Breadth first, left to right
+    Level,
+}
+
+// A handle on a tree node that can be taken apart into the item it yields
+// and handles on its children. Implemented for shared, mutable and owned
+// node handles so one traversal drives iter, iter_mut and into_iter.
+pub(crate) trait Split: Sized {
+    type Item;
+
+    // Returns the node's item and its (left, right) children
+    fn split(self) -> (Self::Item, Option<Self>, Option<Self>);
+}
+
+enum Frame<N: Split> {
+    // Subtree still to be taken apart
+    Expand(N),
+    // Item whose subtrees have been scheduled already
+    Emit(N::Item),
+}
+
+// Traversal of a tree in any Order using an explicit stack (or queue for
+// level order), holding at most O(height) pending frames for the depth
+// first orders
+pub(crate) struct Traversal<N: Split> {
+    order: Order,
+    stack: Vec<Frame<N>>,
+    queue: VecDeque<N>,
+}
+
+impl<N: Split> Traversal<N> {
+    pub(crate) fn new(root: Option<N>, order: Order) -> Traversal<N> {
+        let mut traversal = Traversal { order, stack: Vec::new(), queue: VecDeque::new() };
+        if let Some(root) = root {
+            match order {
+                Order::Level => traversal.queue.push_back(root),
+                _ => traversal.stack.push(Frame::Expand(root)),
+            }
+        }
+        traversal
+    }
+
+    fn push(&mut self, child: Option<N>) {
+        if let Some(child) = child {
+            self.stack.push(Frame::Expand(child));
+        }
+    }
+}
+
+impl<N: Split> Iterator for Traversal<N> {
+    type Item = N::Item;
+
+    fn next(&mut self) -> Option<N::Item> {
+        if self.order == Order::Level {
+            let (item, left, right) = self.queue.pop_front()?.split();
+            self.queue.extend(left);
+            self.queue.extend(right);
+            return Some(item);
+        }
+        loop {
+            let node = match self.stack.pop()? {
+                Frame::Emit(item) => return Some(item),
+                Frame::Expand(node) => node,
+            };
+            let (item, left, right) = node.split();
+            // Frames are pushed in reverse of the order they are visited
+            match self.order {
+                Order::Pre => {
+                    self.push(right);
+                    self.push(left);
+                    return Some(item);
+                },
+                Order::In => {
+                    self.push(right);
+                    self.stack.push(Frame::Emit(item));
+                    self.push(left);
+                },
+                Order::Post => {
+                    self.stack.push(Frame::Emit(item));
+                    self.push(right);
+                    self.push(left);
+                },
+                Order::Level => unreachable!(),
+            }
+        }
+    }
+}
+
+// Returns true if key falls before the lower bound of a range
+pub(crate) fn below<Q: Ord + ?Sized>(key: &Q, lower: Bound<&Q>) -> bool {
+    match lower {
+        Bound::Included(bound) => key < bound,
+        Bound::Excluded(bound) => key <= bound,
+        Bound::Unbounded => false,
+    }
+}
+
+// Returns true if key falls after the upper bound of a range
+pub(crate) fn above<Q: Ord + ?Sized>(key: &Q, upper: Bound<&Q>) -> bool {
+    match upper {
+        Bound::Included(bound) => key > bound,
+        Bound::Excluded(bound) => key >= bound,
+        Bound::Unbounded => false,
+    }
+}
+
+// Double ended in-order walk over the keys of a tree that lie inside a range.
+// Each end keeps a stack of the nodes whose right (front) or left (back)
+// subtrees are still to be visited, so only subtrees overlapping the range
+// are entered. The walk ends once the two ends cross.
+pub(crate) struct Span<N> {
+    front: Vec<N>,
+    back: Vec<N>,
+}
+
+impl<'a, K: Ord + 'a, V: 'a, N> Span<N>
+where
+    N: Split<Item = (&'a K, &'a V)> + Copy,
+{
+    // below and above report whether a key lies outside the range
+    pub(crate) fn new(root: Option<N>, below: impl Fn(&K) -> bool, above: impl Fn(&K) -> bool) -> Span<N> {
+        let mut span = Span { front: Vec::new(), back: Vec::new() };
+        let mut current = root;
+        while let Some(node) = current {
+            let ((key, _), left, right) = node.split();
+            if below(key) {
+                current = right;
+            } else {
+                span.front.push(node);
+                current = left;
+            }
+        }
+        let mut current = root;
+        while let Some(node) = current {
+            let ((key, _), left, right) = node.split();
+            if above(key) {
+                current = left;
+            } else {
+                span.back.push(node);
+                current = right;
+            }
+        }
+        span
+    }
+
+    // Returns true while the next front key does not pass the next back key
+    fn open(&self) -> bool {
+        match (self.front.last(), self.back.last()) {
+            (Some(front), Some(back)) => front.split().0 .0 <= back.split().0 .0,
+            _ => false,
+        }
+    }
+}
+
+impl<'a, K: Ord + 'a, V: 'a, N> Iterator for Span<N>
+where
+    N: Split<Item = (&'a K, &'a V)> + Copy,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.open() {
+            return None;
+        }
+        let (item, _, mut current) = self.front.pop()?.split();
+        while let Some(node) = current {
+            self.front.push(node);
+            current = node.split().1;
+        }
+        Some(item)
+    }
+}
+
+impl<'a, K: Ord + 'a, V: 'a, N> DoubleEndedIterator for Span<N>
+where
+    N: Split<Item = (&'a K, &'a V)> + Copy,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if !self.open() {
+            return None;
+        }
+        let (item, mut current, _) = self.back.pop()?.split();
+        while let Some(node) = current {
+            self.back.push(node);
+            current = node.split().2;
+        }
+        Some(item)
+    }
+}
+
+// Returns the number of levels below and including root, walking one level
+// at a time so deep degenerate trees do not exhaust the call stack
+pub(crate) fn height<N: Split>(root: Option<N>) -> usize {
+    let mut height = 0;
+    let mut level: Vec<N> = root.into_iter().collect();
+    while !level.is_empty() {
+        height += 1;
+        level = level
+            .into_iter()
+            .flat_map(|node| {
+                let (_, left, right) = node.split();
+                left.into_iter().chain(right)
+            })
+            .collect();
+    }
+    height
+}
+
+// Returns the result of combining, node by node from the leaves up, the
+// results for each node's subtrees into one for the node itself, without
+// recursion so deep trees do not exhaust the call stack
+pub(crate) fn fold<N, T>(root: Option<N>, mut combine: impl FnMut(N, Option<T>, Option<T>) -> T) -> Option<T>
+where
+    N: Split + Copy,
+{
+    enum Visit<N> {
+        Enter(N),
+        Leave(N),
+    }
+
+    let mut stack: Vec<Visit<N>> = root.map(Visit::Enter).into_iter().collect();
+    // Results whose parent is not combined yet, a left sibling below the right one
+    let mut done: Vec<T> = Vec::new();
+    while let Some(visit) = stack.pop() {
+        match visit {
+            Visit::Enter(node) => {
+                let (_, left, right) = node.split();
+                stack.push(Visit::Leave(node));
+                stack.extend(right.map(Visit::Enter));
+                stack.extend(left.map(Visit::Enter));
+            },
+            Visit::Leave(node) => {
+                let (_, left, right) = node.split();
+                let right = right.and_then(|_| done.pop());
+                let left = left.and_then(|_| done.pop());
+                let result = combine(node, left, right);
+                done.push(result);
+            },
+        }
+    }
+    done.pop()
+}
+
+// A shared node handle the renderers can walk, exposing what a node stores
+// beyond its key and value
+pub(crate) trait Shape: Split + Copy {
+    // Returns the balance factor the node stores, if its tree keeps one
+    fn balance(self) -> Option<i32> {
+        None
+    }
+
+    // Returns the size of its subtree the node stores, if its tree keeps one
+    fn size(self) -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,229 @@
+//! Checks of the structural invariants each tree keeps.
+//!
+//! Every tree has a `validate()` method returning the [`Violation`]s it finds,
+//! each with the sides taken from the root to the offending node. All trees
+//! must keep their keys in search tree order; trees storing subtree sizes or
+//! AVL balance factors must keep them in step with the actual shape. Built
+//! with the `validate` feature, the crate checks every tree this way after
+//! each insert and remove, and checks each subtree a scapegoat tree rebuilds
+//! is perfectly balanced, panicking on the first change that breaks an
+//! invariant. The checks take time linear in the size of the tree, so they
+//! are off by default.
+//!
+//! A scapegoat tree does not keep every node α-weight-balanced: it lets weight
+//! balance slip until a node lands deeper than log base 1/α of m, where m is
+//! its largest size since the last full rebuild. Such a node is reported, and
+//! so is its lowest ancestor with a child holding more than α of its nodes,
+//! the scapegoat insert should have rebuilt.
+use std::fmt::{self, Debug, Display};
+
+use crate::traversal::Shape;
+
+/// Direction taken from a node to one of its children
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// Towards smaller keys
+    Left,
+    /// Towards larger keys
+    Right,
+}
+
+/// A broken invariant
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The key is not strictly between the keys of the ancestors whose left
+    /// and right subtrees it lies in
+    Order,
+    /// The stored subtree size differs from the number of nodes in the subtree
+    Size {
+        /// Size stored in the node
+        stored: usize,
+        /// Number of nodes in the subtree
+        actual: usize,
+    },
+    /// The stored AVL balance factor differs from the height of the right
+    /// subtree minus the height of the left subtree
+    Balance {
+        /// Balance factor stored in the node
+        stored: i32,
+        /// Actual difference in subtree heights
+        actual: i32,
+    },
+    /// The subtree heights of an AVL node differ by more than one
+    Unbalanced {
+        /// Actual difference in subtree heights
+        actual: i32,
+    },
+    /// The node lies deeper than a scapegoat tree allows
+    TooDeep {
+        /// Number of edges from the root to the node
+        depth: usize,
+        /// Largest depth allowed
+        limit: usize,
+    },
+    /// A child subtree holds more than α of the nodes in the node's subtree,
+    /// while a node below lies too deep
+    Weight {
+        /// Number of nodes in the heavier child subtree
+        child: usize,
+        /// Number of nodes in the node's subtree
+        size: usize,
+    },
+    /// The node count a scapegoat tree keeps differs from its number of nodes
+    Count {
+        /// Count kept by the tree
+        stored: usize,
+        /// Number of nodes in the tree
+        actual: usize,
+    },
+    /// A scapegoat tree's node count n and largest count since the last full
+    /// rebuild m do not satisfy α·m <= n <= m
+    Bounds {
+        /// Number of nodes counted by the tree
+        n: usize,
+        /// Largest n since the last full rebuild
+        m: usize,
+    },
+}
+
+/// A broken invariant and where it was found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation<'a, K> {
+    /// Sides taken from the root to the offending node; empty for the root
+    /// and for problems with the tree as a whole
+    pub path: Vec<Side>,
+    /// Key of the offending node, None for problems with the tree as a whole
+    pub key: Option<&'a K>,
+    /// What is broken
+    pub problem: Problem,
+}
+
+impl<K: Debug> Display for Violation<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key {
+            None => write!(f, "tree: ")?,
+            Some(key) if self.path.is_empty() => write!(f, "root {key:?}: ")?,
+            Some(key) => {
+                for side in &self.path {
+                    f.write_str(if *side == Side::Left { "L" } else { "R" })?;
+                }
+                write!(f, " {key:?}: ")?;
+            },
+        }
+        match &self.problem {
+            Problem::Order => write!(f, "key out of order with its ancestors"),
+            Problem::Size { stored, actual } => write!(f, "stored size {stored}, actual {actual}"),
+            Problem::Balance { stored, actual } => write!(f, "stored balance {stored}, actual {actual}"),
+            Problem::Unbalanced { actual } => write!(f, "subtree heights differ by {actual}"),
+            Problem::TooDeep { depth, limit } => write!(f, "depth {depth} exceeds limit {limit}"),
+            Problem::Weight { child, size } => write!(f, "child holds {child} of {size} nodes"),
+            Problem::Count { stored, actual } => write!(f, "counted {stored} nodes, actual {actual}"),
+            Problem::Bounds { n, m } => write!(f, "n = {n} and m = {m} out of bounds"),
+        }
+    }
+}
+
+// Limits a scapegoat tree places on its shape
+pub(crate) struct Scapegoat {
+    // α = a / b
+    pub(crate) a: u32,
+    pub(crate) b: u32,
+    // Largest depth of any node
+    pub(crate) depth: usize,
+}
+
+// Returns where each violation in result was found and what is broken,
+// leaving out keys, which need not be printable
+pub(crate) fn problems<K>(result: Result<(), Vec<Violation<'_, K>>>) -> Vec<(Vec<Side>, Problem)> {
+    result.err().unwrap_or_default().into_iter().map(|violation| (violation.path, violation.problem)).collect()
+}
+
+// Height and size of a checked subtree, and whether it holds a node that is
+// too deep with no scapegoat found for it yet
+struct Summary {
+    height: usize,
+    size: usize,
+    deep: bool,
+}
+
+// Returns the violations found in the tree below root, walking it depth first
+// with an explicit stack so deep trees do not exhaust the call stack
+pub(crate) fn check<'a, K: Ord + 'a, V: 'a, N>(root: Option<N>, scapegoat: Option<Scapegoat>) -> Vec<Violation<'a, K>>
+where
+    N: Shape<Item = (&'a K, &'a V)>,
+{
+    enum Visit<'a, K, N> {
+        // Node, side it hangs on, and the keys its key must lie strictly between
+        Enter(N, Option<Side>, Option<&'a K>, Option<&'a K>),
+        Leave(N),
+    }
+
+    let mut violations = Vec::new();
+    let mut path = Vec::new();
+    let mut stack = root.map(|root| Visit::Enter(root, None, None, None)).into_iter().collect::<Vec<_>>();
+    // Summaries whose parent is not finished yet, a left sibling below the right one
+    let mut done: Vec<Summary> = Vec::new();
+    while let Some(visit) = stack.pop() {
+        match visit {
+            Visit::Enter(node, side, lower, upper) => {
+                let ((key, _), left, right) = node.split();
+                path.extend(side);
+                let report = |problem| Violation { path: path.clone(), key: Some(key), problem };
+                if lower.is_some_and(|lower| key <= lower) || upper.is_some_and(|upper| key >= upper) {
+                    violations.push(report(Problem::Order));
+                }
+                if let Some(limit) = &scapegoat {
+                    if path.len() > limit.depth {
+                        violations.push(report(Problem::TooDeep { depth: path.len(), limit: limit.depth }));
+                    }
+                }
+                stack.push(Visit::Leave(node));
+                stack.extend(right.map(|right| Visit::Enter(right, Some(Side::Right), Some(key), upper)));
+                stack.extend(left.map(|left| Visit::Enter(left, Some(Side::Left), lower, Some(key))));
+            },
+            Visit::Leave(node) => {
+                let ((key, _), left, right) = node.split();
+                let right = right.and_then(|_| done.pop());
+                let left = left.and_then(|_| done.pop());
+                let height = |summary: &Option<Summary>| summary.as_ref().map_or(0, |summary| summary.height);
+                let size = |summary: &Option<Summary>| summary.as_ref().map_or(0, |summary| summary.size);
+                let mut summary = Summary {
+                    height: 1 + height(&left).max(height(&right)),
+                    size: 1 + size(&left) + size(&right),
+                    deep: scapegoat.as_ref().is_some_and(|limit| path.len() > limit.depth),
+                };
+                let report = |problem| Violation { path: path.clone(), key: Some(key), problem };
+                if let Some(stored) = node.size() {
+                    if stored != summary.size {
+                        violations.push(report(Problem::Size { stored, actual: summary.size }));
+                    }
+                }
+                let actual = height(&right) as i32 - height(&left) as i32;
+                if let Some(stored) = node.balance() {
+                    if stored != actual {
+                        violations.push(report(Problem::Balance { stored, actual }));
+                    }
+                    if actual.abs() > 1 {
+                        violations.push(report(Problem::Unbalanced { actual }));
+                    }
+                }
+                if let Some(limit) = &scapegoat {
+                    let mut found = false;
+                    for child in left.iter().chain(&right).filter(|child| child.deep) {
+                        if (limit.b as usize) * child.size > (limit.a as usize) * summary.size {
+                            violations.push(report(Problem::Weight { child: child.size, size: summary.size }));
+                            found = true;
+                        } else {
+                            summary.deep = true;
+                        }
+                    }
+                    // Rebuilding here would lift every too deep node below
+                    summary.deep &= !found;
+                }
+                done.push(summary);
+                path.pop();
+            },
+        }
+    }
+    violations
+}
@@ -0,0 +1,53 @@
+//! AVL tree heights under sorted and reverse sorted runs of inserts and
+//! deletes, checked against the AVL bound after every step.
+use trees::AvlTree;
+
+const N: u32 = 2000;
+
+// Check that tree is no taller than an AVL tree of its size can be,
+// 1.44·log2(n + 2) levels
+fn check(tree: &AvlTree<u32, ()>, step: &str) {
+    let bound = 1.44 * (tree.len() as f64 + 2.0).log2();
+    assert!(tree.height() as f64 <= bound, "height {} above {bound:.2} after {step}", tree.height());
+}
+
+// Returns a tree holding keys, checking its height after every insert
+fn grown(keys: impl Iterator<Item = u32>) -> AvlTree<u32, ()> {
+    let mut tree = AvlTree::default();
+    for key in keys {
+        assert_eq!(tree.insert(key, ()), None);
+        check(&tree, &format!("inserting {key}"));
+    }
+    assert_eq!(tree.len(), N as usize);
+    tree
+}
+
+#[test]
+fn sorted_runs_keep_the_height_bound() {
+    let ascending: Vec<u32> = (0..N).collect();
+    let descending: Vec<u32> = (0..N).rev().collect();
+    // Every way of growing the tree from one sorted run and shrinking it by
+    // another, from the same end or the other
+    for (grow, shrink) in
+        [(&ascending, &ascending), (&ascending, &descending), (&descending, &ascending), (&descending, &descending)]
+    {
+        let mut tree = grown(grow.iter().copied());
+        for key in shrink {
+            assert_eq!(tree.delete(key), Some((*key, ())));
+            check(&tree, &format!("deleting {key}"));
+        }
+        assert!(tree.is_empty());
+    }
+}
+
+#[test]
+fn extracting_from_either_end_keeps_the_height_bound() {
+    let mut tree = grown(0..N);
+    for step in 0..N {
+        let extracted = if step % 2 == 0 { tree.extract_min() } else { tree.extract_max() };
+        assert!(extracted.is_some());
+        check(&tree, &format!("extraction {step}"));
+    }
+    assert!(tree.is_empty());
+    assert!(tree.validate().is_ok());
+}
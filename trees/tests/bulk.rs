@@ -0,0 +1,100 @@
+//! Building trees from sorted input in one pass, and collecting into them.
+use std::collections::BTreeMap;
+
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+// Levels in a perfectly balanced tree of n nodes
+fn balanced_height(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+fn pairs(n: u32) -> impl Iterator<Item = (u32, u32)> {
+    (0..n).map(|key| (2 * key, key))
+}
+
+fn entries<T: OrderedMap<u32, u32>>(tree: &T) -> Vec<(u32, u32)> {
+    tree.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+#[test]
+fn sorted_input_builds_perfectly_balanced_trees() {
+    for n in 0..300 {
+        let expected: Vec<(u32, u32)> = pairs(n).collect();
+        let height = balanced_height(n as usize);
+
+        let bst = BinarySearchTree::from_sorted_iter(pairs(n));
+        assert_eq!(entries(&bst), expected);
+        assert_eq!(bst.height(), height);
+        assert!(bst.validate().is_ok());
+
+        let avl = AvlTree::from_sorted_iter(pairs(n));
+        assert_eq!(entries(&avl), expected);
+        assert_eq!(avl.height(), height);
+        assert!(avl.validate().is_ok(), "{:?}", avl.validate());
+
+        let sg = SGTree::from_sorted_iter(3, 4, pairs(n));
+        assert_eq!(entries(&sg), expected);
+        assert_eq!(sg.height(), height);
+        assert_eq!(sg.alpha(), (3, 4));
+        assert!(sg.validate().is_ok(), "{:?}", sg.validate());
+    }
+}
+
+#[test]
+fn built_trees_stay_valid_under_updates() {
+    let mut avl = AvlTree::from_sorted_iter(pairs(100));
+    let mut sg = SGTree::from_sorted_iter(2, 3, pairs(100));
+    for key in 0..100 {
+        // Odd keys fall between the built ones; deletes shrink below α·m
+        avl.insert(2 * key + 1, key);
+        sg.insert(2 * key + 1, key);
+        avl.delete(&(4 * key));
+        sg.delete(&(4 * key));
+        assert!(avl.validate().is_ok(), "{:?}", avl.validate());
+        assert!(sg.validate().is_ok(), "{:?}", sg.validate());
+    }
+}
+
+#[test]
+#[should_panic(expected = "strictly ascending")]
+fn unsorted_input_panics() {
+    AvlTree::from_sorted_iter([(1, ()), (3, ()), (2, ())]);
+}
+
+#[test]
+#[should_panic(expected = "strictly ascending")]
+fn repeated_keys_panic() {
+    BinarySearchTree::from_sorted_iter([(1, ()), (1, ())]);
+}
+
+#[test]
+fn collect_and_extend_match_btreemap() {
+    // Sorted, unsorted and repeated keys, where the last value wins
+    let inputs: [Vec<(u32, u32)>; 3] = [
+        pairs(50).collect(),
+        pairs(50).map(|(key, value)| ((key * 37) % 101, value)).collect(),
+        vec![(5, 0), (1, 1), (5, 2), (3, 3), (1, 4)],
+    ];
+    for input in &inputs {
+        let mut model: BTreeMap<u32, u32> = input.iter().copied().collect();
+        let expected: Vec<(u32, u32)> = model.iter().map(|(key, value)| (*key, *value)).collect();
+
+        let mut bst: BinarySearchTree<u32, u32> = input.iter().copied().collect();
+        let mut avl: AvlTree<u32, u32> = input.iter().copied().collect();
+        let mut sg: SGTree<u32, u32> = input.iter().copied().collect();
+        assert_eq!(entries(&bst), expected);
+        assert_eq!(entries(&avl), expected);
+        assert_eq!(entries(&sg), expected);
+
+        let more = [(2, 10), (1000, 11), (5, 12)];
+        model.extend(more);
+        bst.extend(more);
+        avl.extend(more);
+        sg.extend(more);
+        let expected: Vec<(u32, u32)> = model.iter().map(|(key, value)| (*key, *value)).collect();
+        assert_eq!(entries(&bst), expected);
+        assert_eq!(entries(&avl), expected);
+        assert_eq!(entries(&sg), expected);
+        assert!(bst.validate().is_ok() && avl.validate().is_ok() && sg.validate().is_ok());
+    }
+}
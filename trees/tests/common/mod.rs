@@ -0,0 +1,21 @@
+//! Helpers shared by the integration tests, each of which declares
+//! `mod common;`. The benchmark includes this file too.
+
+/// SplitMix64, enough randomness to spread keys and operations
+pub struct Rng(pub u64);
+
+impl Rng {
+    /// Returns the next 64 random bits
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number below bound
+    pub fn below(&mut self, bound: u32) -> u32 {
+        (self.next() % bound as u64) as u32
+    }
+}
@@ -0,0 +1,200 @@
+//! Cursors walking and editing every kind of tree, checked against BTreeMap.
+use std::{collections::BTreeMap, ops::Bound};
+
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+mod common;
+use common::Rng;
+
+// Every key a test inserts lies below LIMIT, so a cursor sought at PAST
+// lands on the ghost
+const LIMIT: u32 = 1 << 30;
+const PAST: u32 = u32::MAX;
+
+// Returns the key after position in model, the least key from the ghost (None)
+fn next(model: &BTreeMap<u32, u32>, position: Option<u32>) -> Option<u32> {
+    let lower = position.map_or(Bound::Unbounded, Bound::Excluded);
+    model.range((lower, Bound::Unbounded)).next().map(|(key, _)| *key)
+}
+
+// Returns the key before position in model, the greatest key from the ghost (None)
+fn prev(model: &BTreeMap<u32, u32>, position: Option<u32>) -> Option<u32> {
+    let upper = position.map_or(Bound::Unbounded, Bound::Excluded);
+    model.range((Bound::Unbounded, upper)).next_back().map(|(key, _)| *key)
+}
+
+// Returns a random key strictly between lower and upper, if there is room
+fn between(rng: &mut Rng, lower: Option<u32>, upper: Option<u32>) -> Option<u32> {
+    let (low, high) = (lower.map_or(0, |key| key + 1), upper.unwrap_or(LIMIT));
+    (low < high).then(|| low + rng.below(high - low))
+}
+
+// The same tests for each kind of tree
+macro_rules! cursor_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            // Multiples of spacing below spacing·n inserted in a scrambled
+            // order, and the same in a BTreeMap
+            fn scrambled(n: u32, spacing: u32) -> ($tree, BTreeMap<u32, u32>) {
+                let mut tree: $tree = $new;
+                let mut model = BTreeMap::new();
+                for i in 0..n {
+                    let key = spacing * ((i * 7919) % n);
+                    OrderedMap::insert(&mut tree, key, i);
+                    model.insert(key, i);
+                }
+                (tree, model)
+            }
+
+            fn assert_matches(tree: &$tree, model: &BTreeMap<u32, u32>) {
+                assert!(tree.validate().is_ok(), "{:?}", tree.validate());
+                let entries: Vec<(u32, u32)> = tree.iter().map(|(key, value)| (*key, *value)).collect();
+                assert_eq!(entries, model.iter().map(|(key, value)| (*key, *value)).collect::<Vec<_>>());
+            }
+
+            #[test]
+            fn walk_both_ways_and_wrap_around() {
+                for n in [0, 1, 2, 7, 100] {
+                    let (tree, model) = scrambled(n, 2);
+                    let expected: Vec<&u32> = model.keys().collect();
+
+                    let mut cursor = tree.cursor_front();
+                    let mut forward = Vec::new();
+                    while let Some(key) = cursor.key() {
+                        assert_eq!(cursor.peek_next().map(|(key, _)| *key), next(&model, Some(*key)));
+                        assert_eq!(cursor.peek_prev().map(|(key, _)| *key), prev(&model, Some(*key)));
+                        forward.push(key);
+                        cursor.move_next();
+                    }
+                    assert_eq!(forward, expected);
+                    // From the ghost, moving on wraps around to the other end
+                    assert_eq!(cursor.peek_next().map(|(key, _)| key), expected.first().copied());
+                    cursor.move_next();
+                    assert_eq!(cursor.key(), expected.first().copied());
+
+                    let mut cursor = tree.cursor_back();
+                    let mut backward = Vec::new();
+                    while let Some(key) = cursor.key() {
+                        backward.push(key);
+                        cursor.move_prev();
+                    }
+                    backward.reverse();
+                    assert_eq!(backward, expected);
+                    cursor.move_prev();
+                    assert_eq!(cursor.key(), expected.last().copied());
+                }
+            }
+
+            #[test]
+            fn cursor_starts_at_the_least_key_not_below() {
+                let (mut tree, model) = scrambled(50, 2);
+                for key in (0..103).chain([PAST]) {
+                    let expected = model.range(key..).next().map(|(key, value)| (*key, *value));
+                    let cursor = tree.cursor(&key);
+                    assert_eq!(cursor.key().zip(cursor.value()).map(|(key, value)| (*key, *value)), expected);
+                    let cursor = tree.cursor_mut(&key);
+                    assert_eq!(cursor.key().copied(), expected.map(|(key, _)| key));
+                }
+            }
+
+            #[test]
+            fn edits_match_btreemap() {
+                for seed in 0..8 {
+                    let mut rng = Rng(seed);
+                    // Keys spread out, leaving room between them
+                    let (mut tree, mut model) = scrambled([0, 1, 10, 200][seed as usize % 4], 1024);
+                    let mut position = model.keys().next().copied();
+                    for _ in 0..20 {
+                        let mut cursor = tree.cursor_mut(&position.unwrap_or(PAST));
+                        for _ in 0..100 {
+                            match rng.below(100) {
+                                0..=29 => {
+                                    cursor.move_next();
+                                    position = next(&model, position);
+                                },
+                                30..=49 => {
+                                    cursor.move_prev();
+                                    position = prev(&model, position);
+                                },
+                                50..=59 => {
+                                    if let Some(value) = cursor.value_mut() {
+                                        *value += 1;
+                                        *model.get_mut(&position.unwrap()).unwrap() += 1;
+                                    }
+                                },
+                                60..=74 => {
+                                    if let Some(key) = between(&mut rng, position, next(&model, position)) {
+                                        cursor.insert_after(key, key);
+                                        model.insert(key, key);
+                                    }
+                                },
+                                75..=89 => {
+                                    if let Some(key) = between(&mut rng, prev(&model, position), position) {
+                                        cursor.insert_before(key, key);
+                                        model.insert(key, key);
+                                    }
+                                },
+                                _ => {
+                                    let removed = position.map(|key| (key, model.remove(&key).unwrap()));
+                                    assert_eq!(cursor.remove_current(), removed);
+                                    position = position.and_then(|key| next(&model, Some(key)));
+                                },
+                            }
+                            assert_eq!(cursor.key().copied(), position);
+                            assert_eq!(cursor.value().copied(), position.map(|key| model[&key]));
+                            assert_eq!(cursor.peek_next().map(|(key, _)| *key), next(&model, position));
+                            assert_eq!(cursor.peek_prev().map(|(key, _)| *key), prev(&model, position));
+                        }
+                        drop(cursor);
+                        assert_matches(&tree, &model);
+                    }
+                }
+            }
+
+            #[test]
+            fn append_then_drain_through_a_cursor() {
+                let mut tree: $tree = $new;
+                let mut model = BTreeMap::new();
+                let mut cursor = tree.cursor_front_mut();
+                for key in 0..1000 {
+                    // Each key goes after the last, where the cursor follows it
+                    cursor.insert_after(key, key);
+                    cursor.move_next();
+                    assert_eq!(cursor.key(), Some(&key));
+                    model.insert(key, key);
+                }
+                drop(cursor);
+                assert_matches(&tree, &model);
+
+                let mut cursor = tree.cursor_front_mut();
+                for key in 0..1000 {
+                    assert_eq!(cursor.remove_current(), Some((key, key)));
+                    model.remove(&key);
+                    if key % 100 == 0 {
+                        drop(cursor);
+                        assert_matches(&tree, &model);
+                        cursor = tree.cursor_front_mut();
+                    }
+                }
+                assert_eq!(cursor.key(), None);
+                assert_eq!(cursor.remove_current(), None);
+                drop(cursor);
+                assert!(tree.is_empty());
+            }
+
+            #[test]
+            #[should_panic(expected = "strictly between")]
+            fn insert_out_of_order_panics() {
+                let (mut tree, _) = scrambled(10, 2);
+                let mut cursor = tree.cursor_mut(&4);
+                cursor.insert_after(7, 0);
+            }
+        }
+    };
+}
+
+cursor_tests!(binary_search_tree, BinarySearchTree<u32, u32>, BinarySearchTree::default());
+cursor_tests!(avl_tree, AvlTree<u32, u32>, AvlTree::default());
+cursor_tests!(scapegoat_tree, SGTree<u32, u32>, SGTree::new(3, 4, None));
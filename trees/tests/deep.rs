@@ -0,0 +1,125 @@
+//! Degenerate trees far deeper than a small stack allows recursion to go.
+//!
+//! Sorted keys grow an unbalanced tree into a chain one node per level. Every
+//! operation runs on a thread whose stack would overflow long before the
+//! bottom of the chain if any of them recursed once per level. Balanced trees
+//! take the same sorted keys by the hundred thousand.
+use std::thread;
+
+use trees::{AvlTree, BinarySearchTree, Order, OrderedMap, SGTree};
+
+const DEPTH: u32 = 5_000;
+const STACK: usize = 64 * 1024;
+// Keys in each sorted run fed to the balanced trees. Building a chain costs
+// time quadratic in its depth, so the unbalanced tree stops at DEPTH; so do
+// the balanced trees when validated after every change.
+const MANY: u32 = if cfg!(feature = "validate") { DEPTH } else { 300_000 };
+
+// Run f on a thread with a small stack, failing the test if it panics
+fn on_small_stack(f: impl FnOnce() + Send + 'static) {
+    thread::Builder::new().stack_size(STACK).spawn(f).unwrap().join().unwrap();
+}
+
+fn chain() -> BinarySearchTree<u32, u32> {
+    let mut tree = BinarySearchTree::default();
+    for key in 0..DEPTH {
+        assert_eq!(tree.insert(key, key), None);
+    }
+    tree
+}
+
+#[test]
+fn insert_search_and_remove_on_a_chain() {
+    on_small_stack(|| {
+        let mut tree = chain();
+        assert_eq!(tree.height(), DEPTH as usize);
+        assert_eq!(tree.insert(DEPTH - 1, 0), Some(DEPTH - 1));
+        assert_eq!(tree.search(&(DEPTH - 1)).len(), DEPTH as usize);
+        assert!(tree.search(&DEPTH).is_empty());
+        assert_eq!(tree.remove(&(DEPTH - 1)), Some((DEPTH - 1, 0)));
+        assert_eq!(tree.remove(&DEPTH), None);
+        assert_eq!(tree.extract_max(), Some((DEPTH - 2, DEPTH - 2)));
+        assert_eq!(tree.extract_min(), Some((0, 0)));
+        assert_eq!(tree.len(), DEPTH as usize - 3);
+        assert!(tree.validate().is_ok());
+    });
+}
+
+#[test]
+fn traverse_a_chain_in_every_order() {
+    on_small_stack(|| {
+        let tree = chain();
+        for order in [Order::Pre, Order::In, Order::Post, Order::Level] {
+            assert_eq!(tree.keys_in(order).count(), DEPTH as usize);
+        }
+        assert_eq!(tree.rank(&(DEPTH - 1)), DEPTH as usize - 1);
+        assert_eq!(tree.range(10..20).count(), 10);
+        let mut owned = tree.into_iter_in(Order::In);
+        assert_eq!(owned.next(), Some((0, 0)));
+        // Dropping the rest of the owning iterator frees the chain below it
+    });
+}
+
+#[test]
+fn drop_a_chain() {
+    on_small_stack(|| {
+        let mut tree = chain();
+        // A chain of left children as well as one of right children
+        for key in (0..DEPTH).map(|key| DEPTH + DEPTH - key) {
+            tree.insert(key, key);
+        }
+        drop(tree);
+    });
+}
+
+// Insert an ascending run of MANY keys, then a descending one above it, and
+// check that every key is there in order
+fn fill<T: OrderedMap<u32, u32>>(tree: &mut T) {
+    for key in (0..MANY).chain((MANY..2 * MANY).rev()) {
+        assert_eq!(tree.insert(key, key), None);
+    }
+    assert_eq!(tree.len(), 2 * MANY as usize);
+    assert!(tree.iter().map(|(key, _)| *key).eq(0..2 * MANY));
+}
+
+// Remove every odd key, then take the keys below MANY from either end,
+// leaving the rest to be dropped with the tree
+fn drain<T: OrderedMap<u32, u32>>(tree: &mut T) {
+    for key in (1..2 * MANY).step_by(2) {
+        assert_eq!(tree.remove(&key), Some((key, key)));
+    }
+    for key in (0..MANY / 2).step_by(2) {
+        assert_eq!(tree.extract_min(), Some((key, key)));
+        assert_eq!(tree.extract_max(), Some((2 * MANY - 2 - key, 2 * MANY - 2 - key)));
+    }
+    assert_eq!(tree.len(), MANY as usize / 2);
+}
+
+#[test]
+fn avl_tree_takes_many_sorted_keys() {
+    on_small_stack(|| {
+        let mut tree = AvlTree::default();
+        fill(&mut tree);
+        assert!(tree.height() as f64 <= 1.44 * f64::from(2 * MANY + 2).log2(), "height {}", tree.height());
+        drain(&mut tree);
+        assert!(tree.validate().is_ok());
+        for order in [Order::Pre, Order::Post, Order::Level] {
+            assert_eq!(tree.keys_in(order).count(), MANY as usize / 2);
+        }
+    });
+}
+
+#[test]
+fn scapegoat_tree_takes_many_sorted_keys() {
+    on_small_stack(|| {
+        let mut tree = SGTree::new(3, 4, None);
+        fill(&mut tree);
+        // Validating checks that no node lies deeper than log base 4/3 of the size
+        assert!(tree.validate().is_ok());
+        drain(&mut tree);
+        assert!(tree.validate().is_ok());
+        for order in [Order::Pre, Order::Post, Order::Level] {
+            assert_eq!(tree.keys_in(order).count(), MANY as usize / 2);
+        }
+    });
+}
@@ -0,0 +1,159 @@
+//! The AVL entry API on present and absent keys, checked against BTreeMap.
+use std::collections::BTreeMap;
+
+use trees::{avl_tree::Entry, AvlTree};
+
+mod common;
+use common::Rng;
+
+// Returns a tree holding key → 10 * key for each of keys
+fn tree_of(keys: &[u32]) -> AvlTree<u32, u32> {
+    let mut tree = AvlTree::default();
+    for &key in keys {
+        tree.insert(key, 10 * key);
+    }
+    tree
+}
+
+fn pairs(tree: &AvlTree<u32, u32>) -> Vec<(u32, u32)> {
+    tree.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+#[test]
+fn entry_reports_whether_the_key_is_present() {
+    let mut tree = tree_of(&[2, 4, 6]);
+    match tree.entry(4) {
+        Entry::Occupied(mut entry) => {
+            assert_eq!((entry.key(), entry.get()), (&4, &40));
+            assert_eq!(entry.insert(41), 40);
+            *entry.get_mut() += 1;
+            assert_eq!(*entry.into_mut(), 42);
+        },
+        Entry::Vacant(_) => panic!("4 is present"),
+    }
+    match tree.entry(5) {
+        Entry::Occupied(_) => panic!("5 is absent"),
+        Entry::Vacant(entry) => {
+            assert_eq!(entry.key(), &5);
+            assert_eq!(entry.into_key(), 5);
+        },
+    }
+    // Dropping a vacant entry leaves the tree as it was
+    assert_eq!(pairs(&tree), [(2, 20), (4, 42), (6, 60)]);
+}
+
+#[test]
+fn or_insert_keeps_present_values_and_fills_absent_ones() {
+    let mut tree = tree_of(&[2, 4, 6]);
+    assert_eq!(*tree.entry(4).or_insert(0), 40);
+    *tree.entry(4).or_insert(0) += 1;
+    assert_eq!(*tree.entry(5).or_insert(50), 50);
+    *tree.entry(5).or_insert(0) += 1;
+    // Vacant inserts at either end return the new value as well
+    *tree.entry(1).or_insert(10) += 1;
+    *tree.entry(7).or_insert(70) += 1;
+    assert_eq!(pairs(&tree), [(1, 11), (2, 20), (4, 41), (5, 51), (6, 60), (7, 71)]);
+    assert!(tree.validate().is_ok());
+}
+
+#[test]
+fn or_insert_with_calls_default_only_for_absent_keys() {
+    let mut tree = tree_of(&[2, 4, 6]);
+    let mut calls = 0;
+    assert_eq!(
+        *tree.entry(4).or_insert_with(|| {
+            calls += 1;
+            0
+        }),
+        40
+    );
+    assert_eq!(calls, 0);
+    assert_eq!(
+        *tree.entry(3).or_insert_with(|| {
+            calls += 1;
+            30
+        }),
+        30
+    );
+    assert_eq!(calls, 1);
+    assert_eq!(*tree.entry(8).or_default(), 0);
+    assert_eq!(pairs(&tree), [(2, 20), (3, 30), (4, 40), (6, 60), (8, 0)]);
+}
+
+#[test]
+fn and_modify_changes_only_present_values() {
+    let mut tree = tree_of(&[2, 4, 6]);
+    assert_eq!(*tree.entry(4).and_modify(|value| *value += 1).or_insert(0), 41);
+    assert_eq!(*tree.entry(5).and_modify(|value| *value += 1).or_insert(0), 0);
+    // The entry is vacant until something is inserted, so modifying it does nothing
+    assert!(matches!(tree.entry(7).and_modify(|_| panic!("7 is absent")), Entry::Vacant(_)));
+    assert_eq!(pairs(&tree), [(2, 20), (4, 41), (5, 0), (6, 60)]);
+}
+
+#[test]
+fn word_count_matches_btreemap() {
+    let text = "the quick brown fox jumps over the lazy dog while the dog sleeps and the fox runs over the hill";
+    let mut tree = AvlTree::default();
+    let mut model = BTreeMap::new();
+    for word in text.split_whitespace() {
+        tree.entry(word).and_modify(|count| *count += 1).or_insert(1);
+        *model.entry(word).or_insert(0) += 1;
+    }
+    assert!(tree.iter().eq(model.iter()));
+    assert_eq!(tree.get("the"), Some(&5));
+    assert!(tree.validate().is_ok());
+}
+
+#[test]
+fn many_vacant_inserts_keep_the_tree_valid() {
+    for seed in 0..10 {
+        let mut rng = Rng(seed);
+        let mut tree = AvlTree::default();
+        let mut model = BTreeMap::new();
+        for step in 0..2000 {
+            let key = rng.below(1000);
+            *tree.entry(key).or_insert_with(|| 0) += step;
+            *model.entry(key).or_insert(0) += step;
+            if step % 100 == 0 {
+                assert!(tree.validate().is_ok(), "seed {seed}, step {step}");
+            }
+        }
+        assert!(tree.validate().is_ok(), "seed {seed}");
+        assert_eq!(tree.len(), model.len());
+        assert!(tree.iter().eq(model.iter()), "seed {seed}");
+    }
+    // Ascending and descending runs make vacant inserts rotate all the time
+    let mut tree = AvlTree::default();
+    for key in (0..500).chain((500..1000).rev()) {
+        assert_eq!(*tree.entry(key).or_insert(key), key);
+    }
+    assert!(tree.validate().is_ok());
+    assert!(tree.iter().map(|(key, value)| (*key, *value)).eq((0..1000).map(|key| (key, key))));
+}
+
+#[test]
+fn vacant_inserts_shape_the_tree_as_insert_does() {
+    // Entries rebalance on the way down, insert on the way back up, and both
+    // must rotate the same nodes
+    for seed in 0..20 {
+        let mut rng = Rng(seed);
+        let (mut by_entry, mut by_insert) = (AvlTree::default(), AvlTree::default());
+        for step in 0..300 {
+            let key = rng.below(400);
+            let present = by_insert.insert(key, step).is_some();
+            match by_entry.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    assert!(present);
+                    entry.insert(step);
+                },
+                Entry::Vacant(entry) => {
+                    assert!(!present);
+                    assert_eq!(*entry.insert(step), step);
+                },
+            }
+            assert!(by_entry.validate().is_ok(), "seed {seed}, step {step}");
+            assert_eq!(by_entry.pre_order(), by_insert.pre_order(), "seed {seed}, step {step}");
+        }
+        assert!(by_entry.iter().eq(by_insert.iter()));
+    }
+}
@@ -0,0 +1,160 @@
+//! Graphviz and SVG diagrams: which nodes and edges a search path
+//! highlights, and where the SVG layout puts the nodes.
+use std::collections::{BTreeMap, BTreeSet};
+
+use trees::export::Diagram;
+use trees::pretty::Annotation;
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+mod common;
+use common::Rng;
+
+// Labels of the highlighted nodes of a DOT document, and the highlighted
+// edges as pairs of labels
+fn highlighted(dot: &str) -> (BTreeSet<String>, BTreeSet<(String, String)>) {
+    let mut labels = BTreeMap::new();
+    let (mut nodes, mut edges) = (BTreeSet::new(), BTreeSet::new());
+    for line in dot.lines().map(str::trim) {
+        let red = line.contains("color=red");
+        if let Some((id, rest)) = line.split_once(" [label=\"") {
+            let label = rest[..rest.find('"').unwrap()].to_string();
+            if red {
+                nodes.insert(label.clone());
+            }
+            labels.insert(id.to_string(), label);
+        } else if let Some((from, to)) = line.split_once(" -> ") {
+            let to = to.split([' ', ';']).next().unwrap();
+            if red {
+                edges.insert((labels[from].clone(), labels[to].clone()));
+            }
+        }
+    }
+    (nodes, edges)
+}
+
+fn strings<const N: usize>(labels: [&str; N]) -> BTreeSet<String> {
+    labels.into_iter().map(String::from).collect()
+}
+
+// Returns the value of the named attribute of an SVG element
+fn attribute(line: &str, name: &str) -> f64 {
+    let start = line.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+    line[start..start + line[start..].find('"').unwrap()].parse().unwrap()
+}
+
+// Check that no two node boxes or notes in an SVG image share any space, and
+// returns the number of highlighted boxes
+fn check_layout(svg: &str) -> usize {
+    // Horizontal extents of everything drawn on each row, keyed by the top
+    // edge the boxes and notes of the row share
+    let mut rows: BTreeMap<String, Vec<(f64, f64)>> = BTreeMap::new();
+    let mut highlighted = 0;
+    for line in svg.lines().map(str::trim) {
+        if line.starts_with("<rect") {
+            let x = attribute(line, "x");
+            rows.entry(attribute(line, "y").to_string()).or_default().push((x, x + attribute(line, "width")));
+            highlighted += usize::from(line.contains("fill=\"#fde2e1\""));
+        } else if line.starts_with("<text") && line.contains("font-size=\"11\"") {
+            // A note, at most 7 pixels per character wide
+            let x = attribute(line, "x");
+            let note = &line[line.find('>').unwrap() + 1..line.find("</text>").unwrap()];
+            rows.entry(attribute(line, "y").to_string()).or_default().push((x, x + 7.0 * note.len() as f64));
+        }
+    }
+    for extents in rows.values_mut() {
+        extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for pair in extents.windows(2) {
+            assert!(pair[0].1 <= pair[1].0, "{pair:?} overlap in\n{svg}");
+        }
+    }
+    highlighted
+}
+
+#[test]
+fn dot_highlights_the_search_path() {
+    let tree = AvlTree::from_sorted_iter((1..=7).map(|key| (key, ())));
+    let path = tree.search(&5);
+    assert_eq!(path, [&4, &6, &5]);
+    let dot = tree.to_dot(&Diagram { path: &path, annotation: None });
+    assert_eq!(
+        highlighted(&dot),
+        (strings(["4", "6", "5"]), [("4", "6"), ("6", "5")].map(|(a, b)| (a.to_string(), b.to_string())).into())
+    );
+    // A failed search returns no path, so nothing is highlighted
+    let dot = tree.to_dot(&Diagram { path: &tree.search(&8), annotation: None });
+    assert_eq!(highlighted(&dot), (BTreeSet::new(), BTreeSet::new()));
+    // Highlighting stops where a path leaves the tree
+    let dot = tree.to_dot(&Diagram { path: &[&4, &2, &5], annotation: None });
+    assert_eq!(highlighted(&dot).0, strings(["4", "2"]));
+    let dot = tree.to_dot(&Diagram { path: &[&2], annotation: None });
+    assert_eq!(highlighted(&dot).0, BTreeSet::new());
+}
+
+#[test]
+fn dot_keeps_single_children_on_their_side() {
+    let mut tree = SGTree::default();
+    tree.insert(1, ());
+    tree.insert(2, ());
+    let path = tree.search(&2);
+    assert_eq!(
+        tree.to_dot(&Diagram { path: &path, annotation: Some(Annotation::Balance) }),
+        [
+            "digraph tree {",
+            "    node [shape=box, style=rounded, fontname=\"monospace\"];",
+            "    n1 [label=\"1\", xlabel=\"+1\", color=red, penwidth=2];",
+            "    n0 [label=\"2\", xlabel=\"0\", color=red, penwidth=2];",
+            "    n1l [style=invis, label=\"\"];",
+            "    n1 -> n1l [style=invis];",
+            "    n1 -> n0 [color=red, penwidth=2];",
+            "}",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn svg_highlights_the_search_path() {
+    let tree = AvlTree::from_sorted_iter((1..=15).map(|key| (key, ())));
+    let path = tree.search(&11);
+    assert_eq!(path.len(), 4);
+    assert_eq!(check_layout(&tree.to_svg(&Diagram { path: &path, annotation: None })), 4);
+    assert_eq!(check_layout(&tree.to_svg(&Diagram::default())), 0);
+    let svg = AvlTree::<u32, ()>::default().to_svg(&Diagram::default());
+    assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n") && !svg.contains("<rect"), "{svg}");
+}
+
+// The same layout checks for each kind of tree
+macro_rules! layout_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            #[test]
+            fn svg_nodes_never_overlap() {
+                let mut rng = Rng(7);
+                for size in [1, 2, 3, 10, 40, 150] {
+                    let mut tree: $tree = $new;
+                    for _ in 0..size {
+                        // Keys of one to six digits, so boxes differ in width
+                        let digits = 1 + rng.below(6);
+                        let key = rng.below(10u32.pow(digits));
+                        OrderedMap::insert(&mut tree, key, ());
+                    }
+                    let key = *tree.iter().nth(rng.below(tree.len() as u32) as usize).unwrap().0;
+                    let path = tree.search(&key);
+                    for annotation in
+                        [None, Some(Annotation::Balance), Some(Annotation::Size), Some(Annotation::Height)]
+                    {
+                        let svg = tree.to_svg(&Diagram { path: &path, annotation });
+                        assert_eq!(check_layout(&svg), path.len());
+                    }
+                }
+            }
+        }
+    };
+}
+
+layout_tests!(binary_search_tree, BinarySearchTree<u32, ()>, BinarySearchTree::default());
+layout_tests!(avl_tree, AvlTree<u32, ()>, AvlTree::default());
+layout_tests!(scapegoat_tree, SGTree<u32, ()>, SGTree::new(3, 4, None));
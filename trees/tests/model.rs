@@ -0,0 +1,441 @@
+//! Model-based randomized tests: every tree runs the same stream of
+//! operations as std's BTreeMap and must agree with it after each step, in
+//! results, traversal outputs and its own invariants.
+//!
+//! Environment variables:
+//!
+//! - `TREES_SEED`: seed of the first case (default 0x5eed)
+//! - `TREES_CASES`: number of random cases per tree (default 200)
+//! - `TREES_TRACE`: file holding a trace to replay instead of random cases
+//!
+//! A failing case is shrunk to a minimal operation sequence and printed as a
+//! trace, one operation per line. Saving it to a file and running
+//! `TREES_TRACE=<file> cargo test --test model` replays it against every tree.
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    env, fmt, fs,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+use trees::{AvlTree, BinarySearchTree, Order, OrderedMap, SGTree};
+
+mod common;
+use common::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Insert(u32, u32),
+    Delete(u32),
+    Search(u32),
+    ExtractMin,
+    ExtractMax,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Insert(key, value) => write!(f, "insert {key} {value}"),
+            Op::Delete(key) => write!(f, "delete {key}"),
+            Op::Search(key) => write!(f, "search {key}"),
+            Op::ExtractMin => write!(f, "extract_min"),
+            Op::ExtractMax => write!(f, "extract_max"),
+        }
+    }
+}
+
+// Returns the operation written on a trace line, as Display writes it
+fn parse(line: &str) -> Option<Op> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |i: usize| words.get(i)?.parse().ok();
+    match *words.first()? {
+        "insert" => Some(Op::Insert(number(1)?, number(2)?)),
+        "delete" => Some(Op::Delete(number(1)?)),
+        "search" => Some(Op::Search(number(1)?)),
+        "extract_min" => Some(Op::ExtractMin),
+        "extract_max" => Some(Op::ExtractMax),
+        _ => None,
+    }
+}
+
+fn trace(ops: &[Op]) -> String {
+    ops.iter().map(|op| format!("{op}\n")).collect()
+}
+
+// Returns a random operation sequence. A small key space makes operations
+// meet existing keys often; a large one grows deeper trees.
+fn generate(rng: &mut Rng) -> Vec<Op> {
+    let keys = [4, 16, 64, 1024][rng.below(4) as usize];
+    let len = 1 + rng.below(300) as usize;
+    (0..len)
+        .map(|_| {
+            let key = rng.below(keys);
+            match rng.below(100) {
+                0..=44 => Op::Insert(key, rng.below(100)),
+                45..=69 => Op::Delete(key),
+                70..=84 => Op::Search(key),
+                85..=92 => Op::ExtractMin,
+                _ => Op::ExtractMax,
+            }
+        })
+        .collect()
+}
+
+// What the harness needs from a tree under test
+trait Subject {
+    fn insert(&mut self, key: u32, value: u32) -> Option<u32>;
+    fn remove(&mut self, key: u32) -> Option<(u32, u32)>;
+    fn get(&self, key: u32) -> Option<u32>;
+    fn extract_min(&mut self) -> Option<(u32, u32)>;
+    fn extract_max(&mut self) -> Option<(u32, u32)>;
+    // Keys visited by search, empty if key was not found
+    fn search(&self, key: u32) -> Vec<u32>;
+    fn entries(&self) -> Vec<(u32, u32)>;
+    fn keys_in(&self, order: Order) -> Vec<u32>;
+    fn len(&self) -> usize;
+    fn validate(&self) -> Result<(), String>;
+}
+
+// The three trees implement Subject alike, the map operations through OrderedMap
+macro_rules! subject {
+    ($($tree:ty),*) => {
+        $(
+            impl Subject for $tree {
+                fn insert(&mut self, key: u32, value: u32) -> Option<u32> {
+                    OrderedMap::insert(self, key, value)
+                }
+
+                fn remove(&mut self, key: u32) -> Option<(u32, u32)> {
+                    OrderedMap::remove(self, &key)
+                }
+
+                fn get(&self, key: u32) -> Option<u32> {
+                    OrderedMap::get(self, &key).copied()
+                }
+
+                fn extract_min(&mut self) -> Option<(u32, u32)> {
+                    OrderedMap::extract_min(self)
+                }
+
+                fn extract_max(&mut self) -> Option<(u32, u32)> {
+                    OrderedMap::extract_max(self)
+                }
+
+                fn search(&self, key: u32) -> Vec<u32> {
+                    self.search(&key).into_iter().copied().collect()
+                }
+
+                fn entries(&self) -> Vec<(u32, u32)> {
+                    self.iter().map(|(key, value)| (*key, *value)).collect()
+                }
+
+                fn keys_in(&self, order: Order) -> Vec<u32> {
+                    self.keys_in(order).copied().collect()
+                }
+
+                fn len(&self) -> usize {
+                    OrderedMap::len(self)
+                }
+
+                fn validate(&self) -> Result<(), String> {
+                    self.validate().map_err(|violations| format!("{violations:?}"))
+                }
+            }
+        )*
+    };
+}
+
+subject!(BinarySearchTree<u32, u32>, AvlTree<u32, u32>, SGTree<u32, u32>);
+
+fn agree<T: PartialEq + fmt::Debug>(what: &str, tree: T, model: T) -> Result<(), String> {
+    if tree == model {
+        return Ok(());
+    }
+    Err(format!("{what}: tree gave {tree:?}, BTreeMap gave {model:?}"))
+}
+
+// Apply op to both maps, then compare their contents in every traversal order
+fn step<T: Subject>(tree: &mut T, model: &mut BTreeMap<u32, u32>, op: Op) -> Result<(), String> {
+    match op {
+        Op::Insert(key, value) => agree("insert", tree.insert(key, value), model.insert(key, value))?,
+        Op::Delete(key) => agree("delete", tree.remove(key), model.remove_entry(&key))?,
+        Op::Search(key) => {
+            let path = tree.search(key);
+            if path.last().is_some_and(|last| *last != key) {
+                return Err(format!("search path {path:?} does not end at {key}"));
+            }
+            agree("search found", !path.is_empty(), model.contains_key(&key))?;
+            agree("get", tree.get(key), model.get(&key).copied())?;
+        },
+        Op::ExtractMin => agree("extract_min", tree.extract_min(), model.pop_first())?,
+        Op::ExtractMax => agree("extract_max", tree.extract_max(), model.pop_last())?,
+    }
+    agree("len", tree.len(), model.len())?;
+    let entries: Vec<(u32, u32)> = model.iter().map(|(key, value)| (*key, *value)).collect();
+    agree("iter", tree.entries(), entries)?;
+    let keys: Vec<u32> = model.keys().copied().collect();
+    agree("in-order keys", tree.keys_in(Order::In), keys.clone())?;
+    // The other orders depend on shape, but must visit every key once
+    for order in [Order::Pre, Order::Post, Order::Level] {
+        let mut visited = tree.keys_in(order);
+        visited.sort_unstable();
+        agree(&format!("{order:?} keys, sorted"), visited, keys.clone())?;
+    }
+    tree.validate()
+}
+
+thread_local! {
+    // Set while running cases whose panics are caught and reported as failures
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+// Keep panics caught by run from printing, leaving other tests' panics alone
+fn quiet_caught_panics() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(Cell::get) {
+                default(info);
+            }
+        }));
+    });
+}
+
+// Run ops against a fresh tree and BTreeMap
+//
+// Returns: Err with the index of the first operation they disagree on, or
+// that panics, and a description
+fn run<T: Subject>(new: &impl Fn() -> T, ops: &[Op]) -> Result<(), (usize, String)> {
+    let mut tree = new();
+    let mut model = BTreeMap::new();
+    for (i, op) in ops.iter().enumerate() {
+        QUIET.with(|quiet| quiet.set(true));
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| step(&mut tree, &mut model, *op)));
+        QUIET.with(|quiet| quiet.set(false));
+        match outcome {
+            Ok(Ok(())) => {},
+            Ok(Err(message)) => return Err((i, message)),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                return Err((i, format!("panicked: {message}")));
+            },
+        }
+    }
+    Ok(())
+}
+
+// Returns variations of op on smaller keys and values
+fn simpler(op: Op) -> Vec<Op> {
+    let smaller = |n: u32| [0, n / 2, n.saturating_sub(1)].into_iter().filter(move |m| *m < n);
+    match op {
+        Op::Insert(key, value) => smaller(value)
+            .map(|value| Op::Insert(key, value))
+            .chain(smaller(key).map(|key| Op::Insert(key, value)))
+            .collect(),
+        Op::Delete(key) => smaller(key).map(Op::Delete).collect(),
+        Op::Search(key) => smaller(key).map(Op::Search).collect(),
+        Op::ExtractMin | Op::ExtractMax => Vec::new(),
+    }
+}
+
+// Shrink a failing sequence: cut it after the failing operation, drop chunks
+// of halving length while it still fails, then simplify the operations left
+//
+// Returns: a sequence from which no single operation can be dropped or simplified
+fn shrink<T: Subject>(new: &impl Fn() -> T, mut ops: Vec<Op>) -> Vec<Op> {
+    if let Err((i, _)) = run(new, &ops) {
+        ops.truncate(i + 1);
+    }
+    let mut chunk = ops.len().div_ceil(2);
+    while chunk > 0 {
+        let mut start = 0;
+        while start < ops.len() {
+            let end = (start + chunk).min(ops.len());
+            let candidate = [&ops[..start], &ops[end..]].concat();
+            if run(new, &candidate).is_err() {
+                ops = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk = if chunk == 1 { 0 } else { chunk.div_ceil(2) };
+    }
+    loop {
+        let mut simplified = false;
+        for i in 0..ops.len() {
+            for op in simpler(ops[i]) {
+                let mut candidate = ops.clone();
+                candidate[i] = op;
+                if run(new, &candidate).is_err() {
+                    ops = candidate;
+                    simplified = true;
+                    break;
+                }
+            }
+        }
+        if !simplified {
+            return ops;
+        }
+    }
+}
+
+// A failing case, shrunk
+struct Failure {
+    seed: u64,
+    ops: Vec<Op>,
+    step: usize,
+    message: String,
+}
+
+// Run cases random cases from seeds first, first + 1, ...
+//
+// Returns: the first failing case, shrunk, or None if all pass
+fn search_failure<T: Subject>(new: &impl Fn() -> T, first: u64, cases: u64) -> Option<Failure> {
+    quiet_caught_panics();
+    for seed in first..first + cases {
+        let ops = generate(&mut Rng(seed));
+        if run(new, &ops).is_ok() {
+            continue;
+        }
+        let ops = shrink(new, ops);
+        let (step, message) = run(new, &ops).expect_err("a shrunk sequence still fails");
+        return Some(Failure { seed, ops, step, message });
+    }
+    None
+}
+
+fn variable(name: &str) -> Option<u64> {
+    let value = env::var(name).ok()?;
+    let value = value.trim();
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    Some(parsed.unwrap_or_else(|_| panic!("{name} must be a number, got {value}")))
+}
+
+// Check the tree new returns against BTreeMap, replaying TREES_TRACE if set
+// and otherwise running random cases, panicking with a replayable trace on
+// the first disagreement
+fn check<T: Subject>(name: &str, new: impl Fn() -> T) {
+    if let Ok(path) = env::var("TREES_TRACE") {
+        let text = fs::read_to_string(&path).unwrap_or_else(|err| panic!("cannot read {path}: {err}"));
+        let ops: Vec<Op> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| parse(line).unwrap_or_else(|| panic!("{path}: cannot parse {line:?}")))
+            .collect();
+        quiet_caught_panics();
+        if let Err((step, message)) = run(&new, &ops) {
+            panic!("{name} disagrees with BTreeMap at step {} ({}): {message}", step + 1, ops[step]);
+        }
+        return;
+    }
+    let first = variable("TREES_SEED").unwrap_or(0x5eed);
+    let cases = variable("TREES_CASES").unwrap_or(200);
+    if let Some(failure) = search_failure(&new, first, cases) {
+        panic!(
+            "{name} disagrees with BTreeMap at step {} ({}): {}\n\
+             seed {:#x}, shrunk to {} operations; replay with TREES_TRACE=<file> cargo test --test model\n\
+             # trace\n{}",
+            failure.step + 1,
+            failure.ops[failure.step],
+            failure.message,
+            failure.seed,
+            failure.ops.len(),
+            trace(&failure.ops)
+        );
+    }
+}
+
+#[test]
+fn binary_search_tree_matches_btreemap() {
+    check("BinarySearchTree", BinarySearchTree::default);
+}
+
+#[test]
+fn avl_tree_matches_btreemap() {
+    check("AvlTree", AvlTree::default);
+}
+
+#[test]
+fn scapegoat_tree_matches_btreemap() {
+    for (a, b) in [(2, 3), (3, 4), (11, 20)] {
+        check(&format!("SGTree with alpha {a}/{b}"), || SGTree::new(a, b, None));
+    }
+}
+
+// A tree whose extract_max takes the minimum once it holds three keys
+struct Faulty(BinarySearchTree<u32, u32>);
+
+impl Subject for Faulty {
+    fn insert(&mut self, key: u32, value: u32) -> Option<u32> {
+        Subject::insert(&mut self.0, key, value)
+    }
+
+    fn remove(&mut self, key: u32) -> Option<(u32, u32)> {
+        Subject::remove(&mut self.0, key)
+    }
+
+    fn get(&self, key: u32) -> Option<u32> {
+        Subject::get(&self.0, key)
+    }
+
+    fn extract_min(&mut self) -> Option<(u32, u32)> {
+        self.0.extract_min()
+    }
+
+    fn extract_max(&mut self) -> Option<(u32, u32)> {
+        if self.0.len() >= 3 {
+            return self.0.extract_min();
+        }
+        self.0.extract_max()
+    }
+
+    fn search(&self, key: u32) -> Vec<u32> {
+        Subject::search(&self.0, key)
+    }
+
+    fn entries(&self) -> Vec<(u32, u32)> {
+        Subject::entries(&self.0)
+    }
+
+    fn keys_in(&self, order: Order) -> Vec<u32> {
+        Subject::keys_in(&self.0, order)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        Subject::validate(&self.0)
+    }
+}
+
+#[test]
+fn failures_shrink_to_a_minimal_replayable_trace() {
+    let new = || Faulty(BinarySearchTree::default());
+    let failure = search_failure(&new, 0x5eed, 200).expect("the faulty tree is caught");
+    assert_eq!(failure.ops.len(), 4, "{}", trace(&failure.ops));
+    assert_eq!(failure.ops[3], Op::ExtractMax);
+    assert_eq!(failure.step, 3);
+    // Three distinct keys, as small as they go
+    let mut keys: Vec<u32> = failure.ops[..3]
+        .iter()
+        .map(|op| match op {
+            Op::Insert(key, 0) => *key,
+            op => panic!("expected an insert of value 0, got {op}"),
+        })
+        .collect();
+    keys.sort_unstable();
+    assert_eq!(keys, [0, 1, 2]);
+    let replayed: Vec<Op> = trace(&failure.ops).lines().map(|line| parse(line).unwrap()).collect();
+    assert_eq!(replayed, failure.ops);
+}
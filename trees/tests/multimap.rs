@@ -0,0 +1,102 @@
+//! AVL multimaps holding repeated keys, checked against a BTreeMap of queues.
+use std::collections::{BTreeMap, VecDeque};
+
+use trees::AvlMultiMap;
+
+mod common;
+use common::Rng;
+
+type Model = BTreeMap<u32, VecDeque<u32>>;
+
+fn entries(model: &Model) -> Vec<(u32, u32)> {
+    model.iter().flat_map(|(key, values)| values.iter().map(|value| (*key, *value))).collect()
+}
+
+// Returns the number of levels an AVL tree of n nodes may have at most
+fn avl_height_limit(n: usize) -> usize {
+    (1.45 * ((n + 2) as f64).log2()) as usize
+}
+
+#[test]
+fn operations_match_a_map_of_queues() {
+    for seed in 0..10 {
+        let mut rng = Rng(seed);
+        let keys = [3, 20, 200][seed as usize % 3];
+        let mut map = AvlMultiMap::new();
+        let mut model = Model::new();
+        for value in 0..2000 {
+            let key = rng.below(keys);
+            match rng.below(10) {
+                0..=5 => {
+                    map.insert_multi(key, value);
+                    model.entry(key).or_default().push_back(value);
+                },
+                6..=7 => {
+                    let expected = model.get_mut(&key).and_then(|values| values.pop_front());
+                    assert_eq!(map.remove_one(&key), expected);
+                },
+                8 => {
+                    let expected: Vec<u32> = model.remove(&key).unwrap_or_default().into();
+                    assert_eq!(map.remove_all(&key), expected);
+                },
+                _ => {
+                    let expected = model.iter().next().map(|(key, values)| (*key, values[0]));
+                    assert_eq!(map.extract_min(), expected);
+                    if let Some((key, _)) = expected {
+                        model.get_mut(&key).unwrap().pop_front();
+                    }
+                },
+            }
+            model.retain(|_, values| !values.is_empty());
+
+            let values = model.get(&key).cloned().unwrap_or_default();
+            assert_eq!(map.count(&key), values.len());
+            assert_eq!(map.contains_key(&key), !values.is_empty());
+            assert_eq!(map.get_all(&key).copied().collect::<Vec<_>>(), Vec::from(values.clone()));
+            assert_eq!(
+                map.get_all(&key).rev().copied().collect::<Vec<_>>(),
+                values.iter().rev().copied().collect::<Vec<_>>()
+            );
+        }
+
+        // Order statistics count every value under a key
+        let expected = entries(&model);
+        assert_eq!(map.len(), expected.len());
+        assert_eq!(map.iter().map(|(key, value)| (*key, *value)).collect::<Vec<_>>(), expected);
+        for (i, (key, value)) in expected.iter().enumerate() {
+            assert_eq!(map.select(i), Some((key, value)));
+            assert_eq!(map.rank(key), expected.partition_point(|(other, _)| other < key));
+        }
+        assert_eq!(map.select(expected.len()), None);
+        assert!(map.height() <= avl_height_limit(map.len()));
+    }
+}
+
+#[test]
+fn equal_keys_come_out_in_insertion_order() {
+    // Events at equal timestamps leave the queue in the order they arrived
+    let events = [(5, 'a'), (1, 'b'), (5, 'c'), (3, 'd'), (1, 'e'), (5, 'f')];
+    let mut queue: AvlMultiMap<u32, char> = events.into_iter().collect();
+    assert_eq!(queue.count(&5), 3);
+    assert_eq!(queue.rank(&3), 2);
+    assert_eq!(queue.rank(&4), 3);
+    let mut order = Vec::new();
+    while let Some((_, event)) = queue.extract_min() {
+        order.push(event);
+    }
+    assert_eq!(order, ['b', 'e', 'd', 'a', 'c', 'f']);
+}
+
+#[test]
+fn a_single_repeated_key_stays_balanced() {
+    let mut map = AvlMultiMap::new();
+    for value in 0..10_000 {
+        map.insert_multi("key", value);
+    }
+    assert_eq!(map.count("key"), 10_000);
+    assert!(map.height() <= avl_height_limit(map.len()));
+    assert_eq!(map.remove_one("key"), Some(0));
+    assert_eq!(map.remove_one("other"), None);
+    assert_eq!(map.remove_all("key").len(), 9_999);
+    assert!(map.is_empty());
+}
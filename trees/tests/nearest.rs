@@ -0,0 +1,91 @@
+//! Floor, ceiling, predecessor and successor lookups on every kind of tree,
+//! checked against BTreeMap.
+use std::{collections::BTreeMap, ops::Bound};
+
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+type Pair = Option<(u32, u32)>;
+
+fn copied(pair: Option<(&u32, &u32)>) -> Pair {
+    pair.map(|(key, value)| (*key, *value))
+}
+
+// The lookups as BTreeMap answers them, in the order floor, ceiling,
+// predecessor, successor
+fn expected(model: &BTreeMap<u32, u32>, key: u32) -> [Pair; 4] {
+    [
+        copied(model.range(..=key).next_back()),
+        copied(model.range(key..).next()),
+        copied(model.range(..key).next_back()),
+        copied(model.range((Bound::Excluded(key), Bound::Unbounded)).next()),
+    ]
+}
+
+// The same tests for each kind of tree
+macro_rules! nearest_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            fn lookups(tree: &$tree, key: u32) -> [Pair; 4] {
+                [
+                    copied(tree.floor(&key)),
+                    copied(tree.ceiling(&key)),
+                    copied(tree.predecessor(&key)),
+                    copied(tree.successor(&key)),
+                ]
+            }
+
+            #[test]
+            fn empty_tree_has_no_neighbours() {
+                let tree: $tree = $new;
+                assert_eq!(lookups(&tree, 0), [None; 4]);
+                assert_eq!(lookups(&tree, u32::MAX), [None; 4]);
+            }
+
+            #[test]
+            fn exact_hits_and_missing_keys() {
+                // Keys 10, 20, ..., 100
+                let mut tree: $tree = $new;
+                for key in [50, 20, 80, 10, 30, 60, 90, 40, 70, 100] {
+                    OrderedMap::insert(&mut tree, key, key + 1);
+                }
+                // An exact hit is its own floor and ceiling but not its own neighbour
+                assert_eq!(lookups(&tree, 50), [Some((50, 51)), Some((50, 51)), Some((40, 41)), Some((60, 61))]);
+                // A missing key between two present ones
+                assert_eq!(lookups(&tree, 55), [Some((50, 51)), Some((60, 61)), Some((50, 51)), Some((60, 61))]);
+                // The smallest and largest keys have nothing below and above them
+                assert_eq!(lookups(&tree, 10), [Some((10, 11)), Some((10, 11)), None, Some((20, 21))]);
+                assert_eq!(lookups(&tree, 100), [Some((100, 101)), Some((100, 101)), Some((90, 91)), None]);
+                // Missing keys beyond either end
+                assert_eq!(lookups(&tree, 5), [None, Some((10, 11)), None, Some((10, 11))]);
+                assert_eq!(lookups(&tree, 105), [Some((100, 101)), None, Some((100, 101)), None]);
+            }
+
+            #[test]
+            fn lookups_match_btreemap() {
+                let mut tree: $tree = $new;
+                let mut model = BTreeMap::new();
+                for i in 0..300u32 {
+                    // Even keys in a scrambled order, so odd ones are always missing
+                    let key = 2 * ((i * 7919) % 300);
+                    OrderedMap::insert(&mut tree, key, i);
+                    model.insert(key, i);
+                    if i % 3 == 0 {
+                        let gone = 2 * ((i * 104_729) % 300);
+                        assert_eq!(OrderedMap::remove(&mut tree, &gone), model.remove_entry(&gone));
+                    }
+                    if i % 50 == 49 {
+                        for key in 0..=601 {
+                            assert_eq!(lookups(&tree, key), expected(&model, key), "key {key}");
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+nearest_tests!(binary_search_tree, BinarySearchTree<u32, u32>, BinarySearchTree::default());
+nearest_tests!(avl_tree, AvlTree<u32, u32>, AvlTree::default());
+nearest_tests!(scapegoat_tree, SGTree<u32, u32>, SGTree::new(3, 4, None));
@@ -0,0 +1,123 @@
+//! Rank, select and median on every kind of tree, checked against a sorted
+//! Vec while keys come and go.
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+mod common;
+use common::Rng;
+
+// The same tests for each kind of tree
+macro_rules! order_statistics_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            // Check every order statistic of tree against the sorted keys it
+            // should hold, each mapped to twice itself
+            fn check(tree: &$tree, sorted: &[u32]) {
+                assert!(tree.validate().is_ok());
+                assert_eq!(tree.len(), sorted.len());
+                for (i, key) in sorted.iter().enumerate() {
+                    assert_eq!(tree.select(i), Some((key, &(2 * key))), "select {i}");
+                    assert_eq!(tree.rank(key), i, "rank {key}");
+                    // A missing key just above counts the present one below it
+                    if sorted.binary_search(&(key + 1)).is_err() {
+                        assert_eq!(tree.rank(&(key + 1)), i + 1, "rank {}", key + 1);
+                    }
+                }
+                assert_eq!(tree.select(sorted.len()), None);
+                assert_eq!(tree.select(usize::MAX), None);
+                let median = sorted.get(sorted.len().saturating_sub(1) / 2);
+                assert_eq!(tree.median().map(|(key, value)| (*key, *value)), median.map(|key| (*key, 2 * key)));
+            }
+
+            #[test]
+            fn empty_tree_has_no_order_statistics() {
+                let tree: $tree = $new;
+                assert_eq!(tree.select(0), None);
+                assert_eq!(tree.rank(&7), 0);
+                assert_eq!(tree.median(), None);
+            }
+
+            #[test]
+            fn median_is_the_lower_middle_key() {
+                let mut tree: $tree = $new;
+                for key in [3, 1] {
+                    OrderedMap::insert(&mut tree, key, 2 * key);
+                }
+                assert_eq!(tree.median(), Some((&1, &2)));
+                OrderedMap::insert(&mut tree, 2, 4);
+                assert_eq!(tree.median(), Some((&2, &4)));
+                OrderedMap::insert(&mut tree, 4, 8);
+                assert_eq!(tree.median(), Some((&2, &4)));
+                check(&tree, &[1, 2, 3, 4]);
+                OrderedMap::remove(&mut tree, &1);
+                assert_eq!(tree.median(), Some((&3, &6)));
+            }
+
+            #[test]
+            fn sorted_runs_keep_sizes_through_rebalancing() {
+                // Ascending and descending inserts make AVL trees rotate and
+                // scapegoat trees rebuild again and again
+                let mut tree: $tree = $new;
+                let mut sorted = Vec::new();
+                for key in (0..200).map(|key| 2 * key) {
+                    OrderedMap::insert(&mut tree, key, 2 * key);
+                    sorted.push(key);
+                    if key % 20 == 0 {
+                        check(&tree, &sorted);
+                    }
+                }
+                for key in (0..200).rev().map(|key| 2 * key + 1001) {
+                    OrderedMap::insert(&mut tree, key, 2 * key);
+                    sorted.insert(200, key);
+                    if key % 20 == 1 {
+                        check(&tree, &sorted);
+                    }
+                }
+                // Removing most keys from the front shrinks a scapegoat tree
+                // enough to rebuild it whole
+                for key in (0..180).map(|key| 2 * key) {
+                    assert_eq!(OrderedMap::remove(&mut tree, &key), Some((key, 2 * key)));
+                    sorted.remove(0);
+                    if key % 20 == 0 {
+                        check(&tree, &sorted);
+                    }
+                }
+                check(&tree, &sorted);
+            }
+
+            #[test]
+            fn order_statistics_match_a_sorted_vec() {
+                for seed in 0..10 {
+                    let mut rng = Rng(seed);
+                    let mut tree: $tree = $new;
+                    let mut sorted: Vec<u32> = Vec::new();
+                    for step in 0..1000 {
+                        // Even keys only, so that odd ones are always missing
+                        let key = 2 * rng.below(300);
+                        let at = sorted.binary_search(&key);
+                        if rng.below(3) < 2 {
+                            assert_eq!(OrderedMap::insert(&mut tree, key, 2 * key).is_some(), at.is_ok());
+                            if let Err(at) = at {
+                                sorted.insert(at, key);
+                            }
+                        } else {
+                            assert_eq!(OrderedMap::remove(&mut tree, &key).is_some(), at.is_ok());
+                            if let Ok(at) = at {
+                                sorted.remove(at);
+                            }
+                        }
+                        if step % 50 == 0 {
+                            check(&tree, &sorted);
+                        }
+                    }
+                    check(&tree, &sorted);
+                }
+            }
+        }
+    };
+}
+
+order_statistics_tests!(binary_search_tree, BinarySearchTree<u32, u32>, BinarySearchTree::default());
+order_statistics_tests!(avl_tree, AvlTree<u32, u32>, AvlTree::default());
+order_statistics_tests!(scapegoat_tree, SGTree<u32, u32>, SGTree::new(3, 4, None));
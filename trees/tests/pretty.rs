@@ -0,0 +1,152 @@
+//! Text drawings of small trees, compared line by line with the expected
+//! pictures.
+use trees::pretty::{Annotation, Style};
+use trees::{AvlTree, BinarySearchTree, SGTree};
+
+fn drawing(lines: &[&str]) -> String {
+    lines.join("\n")
+}
+
+// Returns the unbalanced tree
+//          50
+//      30       70
+//    20  40   60  80
+//  10      45   65  100
+fn lopsided() -> BinarySearchTree<u32, ()> {
+    let mut tree = BinarySearchTree::default();
+    for key in [50, 30, 70, 20, 40, 60, 80, 10, 45, 65, 100] {
+        tree.insert(key, ());
+    }
+    tree
+}
+
+#[test]
+fn every_tree_draws_the_same_perfect_tree_alike() {
+    let expected = drawing(&["     4", "  ┌──┴──┐", "  2     6", "┌─┴─┐ ┌─┴─┐", "1   3 5   7"]);
+    let pairs = || (1..=7).map(|key| (key, ()));
+    assert_eq!(BinarySearchTree::from_sorted_iter(pairs()).render(&Style::default()), expected);
+    assert_eq!(AvlTree::from_sorted_iter(pairs()).render(&Style::default()), expected);
+    assert_eq!(SGTree::from_sorted_iter(2, 3, pairs()).render(&Style::default()), expected);
+    // Display draws in the default style
+    assert_eq!(AvlTree::from_sorted_iter(pairs()).to_string(), expected);
+}
+
+#[test]
+fn ascii_style_draws_connectors_with_plus_and_minus() {
+    let tree = AvlTree::from_sorted_iter((1..=7).map(|key| (key, ())));
+    let style = Style { ascii: true, ..Style::default() };
+    assert_eq!(tree.render(&style), drawing(&["     4", "  +--+--+", "  2     6", "+-+-+ +-+-+", "1   3 5   7"]));
+}
+
+#[test]
+fn empty_tree_draws_a_placeholder() {
+    assert_eq!(BinarySearchTree::<u32, ()>::default().render(&Style::default()), "(empty)");
+    assert_eq!(AvlTree::<u32, ()>::default().to_string(), "(empty)");
+}
+
+#[test]
+fn nodes_with_one_child_bend_towards_it() {
+    assert_eq!(
+        lopsided().render(&Style::default()),
+        drawing(&[
+            "        50",
+            "    ┌───┴────┐",
+            "    30       70",
+            "  ┌─┴─┐    ┌─┴──┐",
+            "  20  40   60   80",
+            "┌─┘   └─┐  └─┐  └─┐",
+            "10      45   65  100",
+        ])
+    );
+}
+
+#[test]
+fn annotations_follow_each_key() {
+    let style = Style { annotation: Some(Annotation::Size), ..Style::default() };
+    assert_eq!(
+        lopsided().render(&style),
+        drawing(&[
+            "               50(n=11)",
+            "         ┌────────┴─────────┐",
+            "      30(n=5)            70(n=5)",
+            "     ┌───┴───┐         ┌────┴────┐",
+            "  20(n=2) 40(n=2)   60(n=2)   80(n=2)",
+            "   ┌─┘       └─┐       └─┐       └─┐",
+            "10(n=1)     45(n=1)   65(n=1)   100(n=1)",
+        ])
+    );
+    let mut tree = BinarySearchTree::default();
+    for key in [3, 1, 5, 4, 2, 6, 7] {
+        tree.insert(key, ());
+    }
+    let style = Style { annotation: Some(Annotation::Balance), ascii: true, ..Style::default() };
+    assert_eq!(
+        tree.render(&style),
+        drawing(&[
+            "     3(+1)",
+            "  +----+----+",
+            "1(+1)     5(+1)",
+            "  +-+    +--+--+",
+            "   2(0) 4(0) 6(+1)",
+            "               +-+",
+            "                7(0)",
+        ])
+    );
+}
+
+#[test]
+fn wide_drawings_wrap_into_strips() {
+    let style = Style { width: Some(10), ..Style::default() };
+    let expected = drawing(&[
+        "        50",
+        "    ┌───┴─",
+        "    30",
+        "  ┌─┴─┐",
+        "  20  40",
+        "┌─┘   └─┐",
+        "10      45",
+        "",
+        "───┐",
+        "   70",
+        " ┌─┴──┐",
+        " 60   80",
+        " └─┐  └─┐",
+        "   65  100",
+    ]);
+    assert_eq!(lopsided().render(&style), expected);
+    // A width given to Display wraps the same way
+    assert_eq!(format!("{:10}", lopsided()), expected);
+    // A width the drawing fits in changes nothing
+    assert_eq!(lopsided().render(&Style { width: Some(20), ..Style::default() }), lopsided().to_string());
+}
+
+#[test]
+fn wrapping_cuts_between_labels_where_it_can() {
+    // Cutting after column 9 would split 50 and 45, so the first strip ends
+    // a column early and both start the next one
+    let style = Style { width: Some(9), ..Style::default() };
+    assert_eq!(
+        lopsided().render(&style),
+        drawing(&[
+            "    ┌───",
+            "    30",
+            "  ┌─┴─┐",
+            "  20  40",
+            "┌─┘   └─",
+            "10",
+            "",
+            "50",
+            "┴────┐",
+            "     70",
+            "   ┌─┴──",
+            "   60",
+            "┐  └─┐",
+            "45   65",
+            "",
+            "┐",
+            "80",
+            "└─┐",
+            " 100",
+        ])
+    );
+}
@@ -0,0 +1,108 @@
+//! Range queries on every kind of tree, walked from either end and checked
+//! against BTreeMap.
+use std::{collections::BTreeMap, ops::Bound};
+
+use trees::{AvlTree, BinarySearchTree, OrderedMap, SGTree};
+
+mod common;
+use common::Rng;
+
+// Returns a random range bound on a key below 220
+fn bound(rng: &mut Rng) -> Bound<u32> {
+    match rng.below(3) {
+        0 => Bound::Included(rng.below(220)),
+        1 => Bound::Excluded(rng.below(220)),
+        _ => Bound::Unbounded,
+    }
+}
+
+// Returns true if BTreeMap accepts the range, which it does unless start
+// lies after end, or both exclude the same key
+fn valid(start: Bound<u32>, end: Bound<u32>) -> bool {
+    match (start, end) {
+        (Bound::Excluded(start), Bound::Excluded(end)) => start < end,
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Included(end) | Bound::Excluded(end)) => start <= end,
+        _ => true,
+    }
+}
+
+// The same tests for each kind of tree
+macro_rules! range_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            // Returns a tree and a model holding the same random keys between 0 and 200
+            fn filled(rng: &mut Rng) -> ($tree, BTreeMap<u32, u32>) {
+                let mut tree: $tree = $new;
+                let mut model = BTreeMap::new();
+                for value in 0..150 {
+                    let key = rng.below(200);
+                    OrderedMap::insert(&mut tree, key, value);
+                    model.insert(key, value);
+                }
+                (tree, model)
+            }
+
+            #[test]
+            fn reversed_ranges_match_btreemap() {
+                let mut rng = Rng(1);
+                for _ in 0..20 {
+                    let (tree, model) = filled(&mut rng);
+                    for _ in 0..50 {
+                        let (start, end) = (bound(&mut rng), bound(&mut rng));
+                        if !valid(start, end) {
+                            continue;
+                        }
+                        let range = (start, end);
+                        assert!(tree.range(range).rev().eq(model.range(range).rev()), "{range:?}");
+                        assert!(tree.range(range).eq(model.range(range)), "{range:?}");
+                    }
+                    assert!(tree.range(..).rev().eq(model.iter().rev()));
+                    assert!(tree.range(..50).rev().eq(model.range(..50).rev()));
+                    assert!(tree.range(150..).rev().eq(model.range(150..).rev()));
+                    assert!(tree.range(60..=90).rev().eq(model.range(60..=90).rev()));
+                }
+            }
+
+            #[test]
+            fn ends_meet_in_the_middle() {
+                let mut rng = Rng(2);
+                for _ in 0..20 {
+                    let (tree, model) = filled(&mut rng);
+                    let (start, end) = (rng.below(100), 100 + rng.below(120));
+                    let (mut ours, mut theirs) = (tree.range(start..end), model.range(start..end));
+                    // Take from either end at random until both run dry together
+                    loop {
+                        let (next, expected) = if rng.below(2) == 0 {
+                            (ours.next(), theirs.next())
+                        } else {
+                            (ours.next_back(), theirs.next_back())
+                        };
+                        assert_eq!(next, expected, "{start}..{end}");
+                        if next.is_none() {
+                            break;
+                        }
+                    }
+                    assert_eq!((ours.next(), ours.next_back()), (None, None));
+                }
+            }
+
+            #[test]
+            fn empty_ranges_yield_nothing() {
+                let mut rng = Rng(3);
+                let (tree, model) = filled(&mut rng);
+                let missing = (0..200).find(|key| !model.contains_key(key)).unwrap();
+                assert_eq!(tree.range(missing..=missing).next_back(), None);
+                assert_eq!(tree.range(50..50).rev().next(), None);
+                assert_eq!(tree.range(200..).next_back(), None);
+                let empty: $tree = $new;
+                assert_eq!(empty.range(..).next_back(), None);
+            }
+        }
+    };
+}
+
+range_tests!(binary_search_tree, BinarySearchTree<u32, u32>, BinarySearchTree::default());
+range_tests!(avl_tree, AvlTree<u32, u32>, AvlTree::default());
+range_tests!(scapegoat_tree, SGTree<u32, u32>, SGTree::new(3, 4, None));
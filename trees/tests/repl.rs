@@ -0,0 +1,117 @@
+//! Scripts saved and loaded by the REPL, and the errors loading them reports.
+use std::{env, fs, path::PathBuf, process};
+
+use trees::repl::{parse_kind, Kind, Repl, Step};
+use trees::scapegoat_tree::valid_alpha;
+
+// Returns a path in the temporary directory unique to this test run
+fn scratch(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("trees-repl-{}-{name}", process::id()))
+}
+
+fn run(repl: &mut Repl, line: &str) -> String {
+    match repl.execute(line) {
+        Ok(Step::Continue(text)) => text,
+        other => panic!("{line}: {other:?}"),
+    }
+}
+
+#[test]
+fn saved_script_loads_into_the_same_tree() {
+    let path = scratch("round-trip");
+    let path = path.to_str().unwrap();
+    let mut repl = Repl::new(Kind::Avl);
+    run(&mut repl, "insert 5 3 8 1 4 7 9");
+    run(&mut repl, "delete 3");
+    run(&mut repl, "# comments and blank lines are skipped");
+    run(&mut repl, "");
+    run(&mut repl, "insert 6");
+    assert_eq!(run(&mut repl, &format!("save {path}")), format!("saved 3 commands to {path}"));
+
+    for kind in [Kind::Bst, Kind::Avl, Kind::Scapegoat(2, 3)] {
+        let mut loaded = Repl::new(kind);
+        let printed = ["5", "3", "8", "1", "4", "7", "9"].map(|key| format!("inserted {key}\n")).concat();
+        assert_eq!(loaded.load(path), Ok(Step::Continue(format!("{printed}deleted 3\ninserted 6"))));
+        assert_eq!(run(&mut loaded, "inorder"), run(&mut repl, "inorder"));
+        assert_eq!(run(&mut loaded, "validate"), "ok");
+    }
+    // Loading through the load command works the same as calling load
+    let mut loaded = Repl::new(Kind::Bst);
+    run(&mut loaded, &format!("load {path}"));
+    assert_eq!(run(&mut loaded, "inorder"), "[1, 4, 5, 6, 7, 8, 9]");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_script_is_reported() {
+    let path = scratch("missing");
+    let path = path.to_str().unwrap();
+    let mut repl = Repl::new(Kind::Avl);
+    let message = repl.load(path).unwrap_err();
+    assert!(message.starts_with(&format!("cannot read {path}: ")), "{message}");
+    let message = repl.execute(&format!("load {path}")).unwrap_err();
+    assert!(message.starts_with(&format!("cannot read {path}: ")), "{message}");
+    assert_eq!(run(&mut repl, "len"), "0");
+}
+
+#[test]
+fn malformed_line_stops_the_script_at_its_location() {
+    let path = scratch("malformed");
+    let path = path.to_str().unwrap();
+    fs::write(path, "insert 1 2\n\ninsert 3 x\ninsert 4\n").unwrap();
+    let mut repl = Repl::new(Kind::Avl);
+    assert_eq!(repl.load(path), Err(format!("{path}:3: invalid key x")));
+    // Commands before the bad line ran, the bad line and those after did not
+    assert_eq!(run(&mut repl, "inorder"), "[1, 2]");
+
+    fs::write(path, "insert 1\nfrobnicate\n").unwrap();
+    assert_eq!(Repl::new(Kind::Bst).load(path), Err(format!("{path}:2: unknown command frobnicate, see help")));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn script_loading_itself_is_rejected() {
+    let path = scratch("self");
+    let path = path.to_str().unwrap();
+    fs::write(path, format!("insert 1\nload {path}\ninsert 2\n")).unwrap();
+    let mut repl = Repl::new(Kind::Avl);
+    assert_eq!(repl.load(path), Err(format!("{path}:2: {path} is already being loaded")));
+    assert_eq!(run(&mut repl, "inorder"), "[1]");
+
+    // A cycle through another script is caught as well, however it is named
+    let other = scratch("other");
+    let other = other.to_str().unwrap();
+    fs::write(path, format!("insert 1\nload {other}\n")).unwrap();
+    let detour = env::temp_dir().join(".").join(scratch("self").file_name().unwrap());
+    fs::write(other, format!("insert 2\nload {}\n", detour.display())).unwrap();
+    let message = Repl::new(Kind::Bst).load(path).unwrap_err();
+    assert!(message.starts_with(&format!("{path}:2: {other}:2: ")), "{message}");
+    assert!(message.ends_with("is already being loaded"), "{message}");
+
+    // The same script may still be loaded again once it has finished
+    fs::write(other, "insert 3\n").unwrap();
+    fs::write(path, format!("load {other}\nload {other}\n")).unwrap();
+    let mut repl = Repl::new(Kind::Bst);
+    assert_eq!(repl.load(path), Ok(Step::Continue("inserted 3\nalready present: 3".to_string())));
+    fs::remove_file(path).unwrap();
+    fs::remove_file(other).unwrap();
+}
+
+#[test]
+fn kinds_parse_as_the_backend_command_names_them() {
+    assert_eq!(parse_kind("bst", &[]), Ok(Kind::Bst));
+    assert_eq!(parse_kind("avl", &[]), Ok(Kind::Avl));
+    assert_eq!(parse_kind("sg", &[]), Ok(Kind::Scapegoat(2, 3)));
+    assert_eq!(parse_kind("sg", &["3", "4"]), Ok(Kind::Scapegoat(3, 4)));
+    assert!(parse_kind("avl", &["3", "4"]).is_err());
+    assert!(parse_kind("sg", &["3"]).is_err());
+    // Alpha must lie strictly between 1/2 and 1, as SGTree::new requires
+    for (a, b) in [(1, 2), (1, 4), (4, 4), (5, 4), (0, 0)] {
+        assert!(!valid_alpha(a, b), "{a}/{b}");
+        assert_eq!(
+            parse_kind("sg", &[&a.to_string(), &b.to_string()]),
+            Err(format!("alpha = {a} / {b} must lie strictly between 1/2 and 1"))
+        );
+    }
+    assert!(valid_alpha(2, 3) && valid_alpha(51, 100) && valid_alpha(99, 100));
+}
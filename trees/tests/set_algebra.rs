@@ -0,0 +1,137 @@
+//! Set operations on binary search trees, checked against BTreeSet.
+use std::{cell::Cell, cmp::Ordering, collections::BTreeSet};
+
+use trees::BinarySearchTree;
+
+type Set = BinarySearchTree<u32, ()>;
+
+// A consuming set operation
+type Operation = fn(Set, Set) -> Set;
+
+// About every third key below limit, chosen by salt
+fn sample(limit: u32, salt: u32) -> BTreeSet<u32> {
+    (0..limit).filter(|key| (key.wrapping_mul(2_654_435_761) ^ salt).is_multiple_of(3)).collect()
+}
+
+fn tree(keys: &BTreeSet<u32>) -> Set {
+    let mut tree = Set::default();
+    let mut keys: Vec<u32> = keys.iter().copied().collect();
+    // Middle keys first, so the tree is not a chain
+    while !keys.is_empty() {
+        let key = keys.remove(keys.len() / 2);
+        tree.insert(key, ());
+    }
+    tree
+}
+
+fn keys<'a>(iter: impl Iterator<Item = (&'a u32, &'a ())>) -> Vec<u32> {
+    iter.map(|(key, _)| *key).collect()
+}
+
+// Pairs of sets of similar and of very different sizes
+fn pairs() -> Vec<(BTreeSet<u32>, BTreeSet<u32>)> {
+    let mut pairs = Vec::new();
+    for (a, b) in [(0, 0), (0, 50), (50, 0), (60, 60), (300, 40), (5, 2000), (2000, 5), (1000, 1000)] {
+        for salt in 0..3 {
+            pairs.push((sample(a, salt), sample(b, salt + 1)));
+        }
+    }
+    // Subsets and disjoint sets
+    pairs.push((sample(50, 0), sample(50, 0)));
+    pairs.push(((0..5).collect(), (0..200).collect()));
+    pairs.push(((0..100).collect(), (100..200).collect()));
+    pairs.push(((0..5).map(|key| 2 * key).collect(), (0..500).map(|key| 2 * key + 1).collect()));
+    pairs
+}
+
+#[test]
+fn lazy_operations_match_btreeset() {
+    for (a, b) in pairs() {
+        let (x, y) = (tree(&a), tree(&b));
+        assert_eq!(keys(x.union(&y)), a.union(&b).copied().collect::<Vec<_>>());
+        assert_eq!(keys(x.intersection(&y)), a.intersection(&b).copied().collect::<Vec<_>>());
+        assert_eq!(keys(x.difference(&y)), a.difference(&b).copied().collect::<Vec<_>>());
+        assert_eq!(keys(x.symmetric_difference(&y)), a.symmetric_difference(&b).copied().collect::<Vec<_>>());
+        assert_eq!(x.is_subset(&y), a.is_subset(&b), "{a:?} {b:?}");
+        assert_eq!(x.is_disjoint(&y), a.is_disjoint(&b));
+    }
+}
+
+#[test]
+fn consuming_operations_match_btreeset() {
+    for (a, b) in pairs() {
+        let operations: [(Operation, BTreeSet<u32>); 4] = [
+            (Set::into_union, &a | &b),
+            (Set::into_intersection, &a & &b),
+            (Set::into_difference, &a - &b),
+            (Set::into_symmetric_difference, &a ^ &b),
+        ];
+        for (operation, expected) in operations {
+            let result = operation(tree(&a), tree(&b));
+            assert_eq!(keys(result.iter()), expected.iter().copied().collect::<Vec<_>>());
+            assert!(result.validate().is_ok());
+            // Results are perfectly balanced
+            assert_eq!(result.height(), (usize::BITS - result.len().leading_zeros()) as usize);
+        }
+    }
+}
+
+#[test]
+fn values_come_from_the_first_tree() {
+    let a: BinarySearchTree<u32, char> = [(1, 'a'), (2, 'a'), (3, 'a')].into_iter().collect();
+    let b: BinarySearchTree<u32, char> = [(2, 'b'), (3, 'b'), (4, 'b')].into_iter().collect();
+    let union: Vec<(u32, char)> = a.union(&b).map(|(key, value)| (*key, *value)).collect();
+    assert_eq!(union, [(1, 'a'), (2, 'a'), (3, 'a'), (4, 'b')]);
+    let common: Vec<char> = b.intersection(&a).map(|(_, value)| *value).collect();
+    assert_eq!(common, ['b', 'b']);
+    let union: Vec<(u32, char)> = b.into_union(a).into_iter().collect();
+    assert_eq!(union, [(1, 'a'), (2, 'b'), (3, 'b'), (4, 'b')]);
+}
+
+thread_local! {
+    static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// A key counting how often it is compared
+#[derive(PartialEq, Eq)]
+struct Counted(u32);
+
+impl PartialOrd for Counted {
+    fn partial_cmp(&self, other: &Counted) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Counted {
+    fn cmp(&self, other: &Counted) -> Ordering {
+        COMPARISONS.set(COMPARISONS.get() + 1);
+        self.0.cmp(&other.0)
+    }
+}
+
+#[test]
+fn small_sets_never_search_a_chain() {
+    // Sorted inserts make the large tree a chain as deep as it is large, so
+    // searching it once per key of the small tree would cost far more than
+    // walking both side by side
+    const LARGE: u32 = 3000;
+    let mut chain = BinarySearchTree::default();
+    for key in 0..LARGE {
+        chain.insert(Counted(key), ());
+    }
+    assert_eq!(chain.height(), LARGE as usize);
+    let small: BinarySearchTree<Counted, ()> =
+        [LARGE - 3, LARGE - 2, LARGE + 1].map(|key| (Counted(key), ())).into_iter().collect();
+    let walk = (LARGE + 3) as usize;
+    COMPARISONS.set(0);
+    let common: Vec<u32> = small.intersection(&chain).map(|(key, _)| key.0).collect();
+    assert_eq!(common, [LARGE - 3, LARGE - 2]);
+    assert!(COMPARISONS.get() <= walk, "{} comparisons", COMPARISONS.get());
+    COMPARISONS.set(0);
+    assert_eq!(chain.intersection(&small).count(), 2);
+    assert!(COMPARISONS.get() <= walk, "{} comparisons", COMPARISONS.get());
+    COMPARISONS.set(0);
+    let only: Vec<u32> = small.difference(&chain).map(|(key, _)| key.0).collect();
+    assert_eq!(only, [LARGE + 1]);
+    assert!(COMPARISONS.get() <= walk, "{} comparisons", COMPARISONS.get());
+}
@@ -0,0 +1,126 @@
+//! Splitting AVL trees by key and joining them back together.
+use std::collections::BTreeMap;
+
+use trees::AvlTree;
+
+// Keys 0, 3, 6, ... inserted in a scrambled order, so trees have varied shapes
+fn scrambled(n: u32, salt: u32) -> (AvlTree<u32, u32>, BTreeMap<u32, u32>) {
+    let mut tree = AvlTree::default();
+    let mut model = BTreeMap::new();
+    for i in 0..n {
+        let key = 3 * ((i * 7919 + salt) % n.max(1));
+        tree.insert(key, i);
+        model.insert(key, i);
+    }
+    (tree, model)
+}
+
+fn entries(tree: &AvlTree<u32, u32>) -> Vec<(u32, u32)> {
+    tree.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+fn model_entries(model: &BTreeMap<u32, u32>) -> Vec<(u32, u32)> {
+    model.iter().map(|(key, value)| (*key, *value)).collect()
+}
+
+fn assert_valid<V>(tree: &AvlTree<u32, V>) {
+    if let Err(violations) = tree.validate() {
+        panic!("{}", violations.iter().map(|violation| format!("{violation}\n")).collect::<String>());
+    }
+}
+
+#[test]
+fn split_off_matches_btreemap() {
+    for n in [0, 1, 2, 3, 10, 57, 300] {
+        // Split keys below, between, on and above the keys present
+        for key in (0..3 * n + 3).step_by(2) {
+            let (mut tree, mut model) = scrambled(n, key);
+            let upper = tree.split_off(&key);
+            let model_upper = model.split_off(&key);
+            assert_valid(&tree);
+            assert_valid(&upper);
+            assert_eq!(entries(&tree), model_entries(&model));
+            assert_eq!(entries(&upper), model_entries(&model_upper));
+        }
+    }
+}
+
+#[test]
+fn join_trees_of_any_heights() {
+    for left_size in [0, 1, 2, 5, 40, 500] {
+        for right_size in [0, 1, 3, 33, 800] {
+            let left: AvlTree<u32, u32> = (0..left_size).map(|key| (key, key)).collect();
+            let right = (0..right_size).map(|key| (left_size + 1 + key, key)).rev().collect();
+            let joined = AvlTree::join(left, (left_size, 0), right);
+            assert_valid(&joined);
+            let keys: Vec<u32> = joined.keys().copied().collect();
+            assert_eq!(keys, (0..left_size + 1 + right_size).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "keys of left must be less than key")]
+fn join_out_of_order_panics() {
+    let left: AvlTree<u32, ()> = [(5, ())].into_iter().collect();
+    AvlTree::join(left, (3, ()), AvlTree::default());
+}
+
+#[test]
+fn split_then_append_restores_the_tree() {
+    let (mut tree, model) = scrambled(1000, 17);
+    for key in [0, 1, 1500, 2997, 3000] {
+        let mut upper = tree.split_off(&key);
+        tree.append(&mut upper);
+        assert!(upper.is_empty());
+        assert_valid(&tree);
+        assert_eq!(entries(&tree), model_entries(&model));
+        // The lower half appended to the upper half works as well
+        let mut lower = tree.split_off(&key);
+        lower.append(&mut tree);
+        tree = lower;
+        assert_valid(&tree);
+        assert_eq!(entries(&tree), model_entries(&model));
+    }
+}
+
+#[test]
+fn append_overlapping_trees_matches_btreemap() {
+    for (n, m) in [(0, 5), (5, 0), (10, 10), (200, 30), (30, 200)] {
+        let (mut tree, mut model) = scrambled(n, 1);
+        let (mut other, mut other_model) = scrambled(m, 2);
+        // Shift other so some of its keys land between those of tree
+        let mut shifted = AvlTree::default();
+        let mut shifted_model = BTreeMap::new();
+        for (key, value) in other_model.iter() {
+            shifted.insert(key + 1 + key % 2, value + 1000);
+            shifted_model.insert(key + 1 + key % 2, value + 1000);
+        }
+        other.append(&mut shifted);
+        other_model.append(&mut shifted_model);
+        tree.append(&mut other);
+        model.append(&mut other_model);
+        assert!(other.is_empty());
+        assert_valid(&tree);
+        assert_eq!(entries(&tree), model_entries(&model));
+    }
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn split_and_join_rotate_logarithmically_often() {
+    use trees::stats;
+
+    let mut tree: AvlTree<u32, ()> = (1..100_000).map(|key| (key, ())).collect();
+    let height = tree.height() as u64;
+    stats::reset();
+    let upper = tree.split_off(&31_337);
+    assert!(stats::counters().rotations <= 4 * height, "{:?}", stats::counters());
+    stats::reset();
+    let tree = AvlTree::join(tree, (1_000_000, ()), AvlTree::default());
+    let tree = AvlTree::join(AvlTree::default(), (0, ()), tree);
+    assert!(stats::counters().rotations <= 4, "{:?}", stats::counters());
+    assert_valid(&upper);
+    assert_valid(&tree);
+    assert_eq!(tree.len() + upper.len(), 100_001);
+}
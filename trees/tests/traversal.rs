@@ -0,0 +1,117 @@
+//! Every traversal order on a tree of known shape, borrowed, mutably
+//! borrowed and owned, compared with the exact sequences expected.
+use trees::{AvlTree, BinarySearchTree, Order, SGTree};
+
+// Inserted in this order, the keys make the same tree in every kind of tree,
+// as it is balanced enough that neither AVL nor scapegoat trees rebalance it
+//          50
+//      30       70
+//    20  40   60  80
+//  10      45   65  100
+const INSERTS: [u32; 11] = [50, 30, 70, 20, 40, 60, 80, 10, 45, 65, 100];
+
+// Returns the keys of the tree above in the given order
+fn expected(order: Order) -> [u32; 11] {
+    match order {
+        Order::Pre => [50, 30, 20, 10, 40, 45, 70, 60, 65, 80, 100],
+        Order::In => [10, 20, 30, 40, 45, 50, 60, 65, 70, 80, 100],
+        Order::Post => [10, 20, 45, 40, 30, 65, 60, 100, 80, 70, 50],
+        Order::Level => [50, 30, 70, 20, 40, 60, 80, 10, 45, 65, 100],
+    }
+}
+
+const ORDERS: [Order; 4] = [Order::Pre, Order::In, Order::Post, Order::Level];
+
+#[test]
+fn key_lists_follow_the_shape() {
+    let mut bst = BinarySearchTree::default();
+    let mut avl = AvlTree::default();
+    for key in INSERTS {
+        bst.insert(key, ());
+        avl.insert(key, ());
+    }
+    for (keys, order) in [
+        (bst.pre_order(), Order::Pre),
+        (bst.in_order(), Order::In),
+        (bst.post_order(), Order::Post),
+        (bst.bft(), Order::Level),
+        (avl.pre_order(), Order::Pre),
+        (avl.in_order(), Order::In),
+        (avl.post_order(), Order::Post),
+        (avl.bft(), Order::Level),
+    ] {
+        assert!(keys.into_iter().eq(&expected(order)), "{order:?}");
+    }
+}
+
+// The same tests for each kind of tree
+macro_rules! traversal_tests {
+    ($kind:ident, $tree:ty, $new:expr) => {
+        mod $kind {
+            use super::*;
+
+            // Returns the tree above with each key mapped to twice itself
+            fn shaped() -> $tree {
+                let mut tree: $tree = $new;
+                for key in INSERTS {
+                    tree.insert(key, 2 * key);
+                }
+                tree
+            }
+
+            #[test]
+            fn borrowing_iterators_follow_the_shape() {
+                let tree = shaped();
+                for order in ORDERS {
+                    let keys = expected(order);
+                    assert!(tree.keys_in(order).eq(&keys), "{order:?}");
+                    assert!(tree.values_in(order).copied().eq(keys.map(|key| 2 * key)), "{order:?}");
+                    assert!(tree.iter_in(order).map(|(key, value)| (*key, *value)).eq(keys.map(|key| (key, 2 * key))));
+                }
+                assert!(tree.iter().map(|(key, _)| key).eq(&expected(Order::In)));
+            }
+
+            #[test]
+            fn mutable_iterators_follow_the_shape() {
+                for order in ORDERS {
+                    let mut tree = shaped();
+                    // Number the values in the order they are visited
+                    for (i, (key, value)) in tree.iter_mut_in(order).enumerate() {
+                        assert_eq!(*key, expected(order)[i], "{order:?}");
+                        *value = i as u32;
+                    }
+                    assert!(tree.values_in(order).copied().eq(0..11), "{order:?}");
+                    assert!(tree.keys_in(order).eq(&expected(order)), "{order:?}");
+                }
+                let mut tree = shaped();
+                for (key, value) in tree.iter_mut() {
+                    *value += key;
+                }
+                assert!(tree
+                    .iter()
+                    .map(|(key, value)| (*key, *value))
+                    .eq(expected(Order::In).map(|key| (key, 3 * key))));
+            }
+
+            #[test]
+            fn owning_iterators_follow_the_shape() {
+                for order in ORDERS {
+                    let pairs: Vec<(u32, u32)> = shaped().into_iter_in(order).collect();
+                    assert_eq!(pairs, expected(order).map(|key| (key, 2 * key)), "{order:?}");
+                    // Dropping a partly consumed iterator frees the rest
+                    let mut owned = shaped().into_iter_in(order);
+                    assert_eq!(owned.next(), Some((expected(order)[0], 2 * expected(order)[0])));
+                }
+                for order in ORDERS {
+                    let empty: $tree = $new;
+                    assert_eq!(empty.keys_in(order).next(), None);
+                    assert_eq!(empty.into_iter_in(order).next(), None);
+                }
+            }
+        }
+    };
+}
+
+traversal_tests!(binary_search_tree, BinarySearchTree<u32, u32>, BinarySearchTree::default());
+traversal_tests!(avl_tree, AvlTree<u32, u32>, AvlTree::default());
+traversal_tests!(scapegoat_tree, SGTree<u32, u32>, SGTree::new(3, 4, None));